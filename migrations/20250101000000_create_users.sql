@@ -0,0 +1,11 @@
+CREATE TABLE users (
+    business_id TEXT NOT NULL,
+    uuid TEXT NOT NULL,
+    first_name TEXT NOT NULL,
+    last_name TEXT NOT NULL,
+    email TEXT NOT NULL COLLATE NOCASE,
+    enabled INTEGER NOT NULL CHECK (enabled IN (0, 1)),
+    activated INTEGER NOT NULL CHECK (activated IN (0, 1)),
+    PRIMARY KEY (business_id, uuid),
+    CONSTRAINT users_email_unique UNIQUE (business_id, email)
+);
@@ -0,0 +1,5 @@
+-- Timestamps are fixed-width UTC text (see `repository::query::timestamp`) so that they sort as text.
+ALTER TABLE users ADD COLUMN created_at TEXT NOT NULL DEFAULT '1970-01-01T00:00:00.000000Z';
+
+CREATE INDEX users_by_name ON users (business_id, lower(last_name), lower(first_name), uuid);
+CREATE INDEX users_by_created_at ON users (business_id, created_at, uuid);
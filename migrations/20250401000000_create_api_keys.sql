@@ -0,0 +1,13 @@
+-- Only a hash of each key's secret is kept; see `api_keys::StoredApiKey`.
+CREATE TABLE api_keys (
+    prefix TEXT PRIMARY KEY NOT NULL,
+    business_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    scopes TEXT NOT NULL,
+    secret_hash TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT,
+    last_used_at TEXT
+);
+
+CREATE INDEX api_keys_by_business ON api_keys (business_id, prefix);
@@ -0,0 +1,17 @@
+-- `settings` is a JSON object; missing settings take their defaults when read.
+CREATE TABLE businesses (
+    uuid TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL CHECK (status IN ('active', 'archived')),
+    settings TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Businesses that users and API keys were stored under before they were records of their
+-- own, named after their id like `businesses::Business::placeholder`.
+INSERT INTO businesses (uuid, name, slug, status, settings, created_at, updated_at)
+SELECT business_id, business_id, business_id, 'active', '{}', MIN(created_at), MIN(created_at)
+FROM (SELECT business_id, created_at FROM users UNION ALL SELECT business_id, created_at FROM api_keys)
+GROUP BY business_id;
@@ -0,0 +1,266 @@
+use std::time::Duration;
+
+use axum::{extract::State, response::IntoResponse, http::{header, StatusCode}, Json};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::Principal;
+use crate::businesses;
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{self, ApiKeyPath, BusinessPath};
+use crate::policy::Scope;
+use crate::repository::{ApiKeyRepository, RepositoryError};
+use crate::AppState;
+
+/// Header machine clients send their key in.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Start of every key's prefix, so that leaked keys are easy to recognise and scan for.
+const KEY_PREFIX: &str = "ak_";
+
+/// Prefixes drawn before giving up on finding an unused one, which only a broken random
+/// source or store should exhaust.
+const PREFIX_ATTEMPTS: usize = 5;
+
+/// How stale a key's `lastUsedAt` may get, so that a busy key does not write on every request.
+const LAST_USED_RESOLUTION: Duration = Duration::from_secs(60);
+
+/// API Key
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// A key a machine client authenticates with; the secret part is never shown again.
+pub struct ApiKey {
+    #[schema(
+        example = "ak_3f9c2a71b04e",
+    )]
+    /// Public part of the key, which identifies it.
+    pub prefix: String,
+    #[schema(
+        example = "Billing export",
+    )]
+    /// What the key is for.
+    pub name: String,
+    #[schema(
+        inline,
+    )]
+    /// What the key may do, within its business.
+    pub scopes: Vec<Scope>,
+    #[schema(
+        example = "2025-01-01T09:30:00Z",
+    )]
+    /// When the key was created.
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// When the key stops working; it never does if absent.
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// When the key was last used, to the minute.
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// New API Key
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Details of an API key to create; the server generates the key itself.
+pub struct CreateApiKey {
+    #[schema(
+        example = "Billing export",
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// What the key is for.
+    pub name: String,
+    #[schema(
+        inline,
+        min_items = 1,
+    )]
+    /// What the key may do; at most what the caller creating it may do.
+    pub scopes: Vec<Scope>,
+    #[serde(default)]
+    #[schema(
+        example = "2030-01-01T00:00:00Z",
+    )]
+    /// When the key stops working; it never does if absent.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Created API Key
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// A newly created API key, with the only copy of its plaintext.
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    #[schema(
+        example = "ak_3f9c2a71b04e.Jx2m9oQ0YkqRZp4vT1c8b7nA6sWfHdLe3uGiK5yMhXo",
+    )]
+    /// The key to send in `X-Api-Key`. It is not stored and cannot be shown again.
+    pub key: String,
+}
+
+/// An API key as stored: its business and a hash of its secret rather than the secret itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredApiKey {
+    pub business_id: Uuid,
+    pub api_key: ApiKey,
+    /// Base64 SHA-256 of the secret part. The secret is random and long, so a slow password
+    /// hash would add nothing.
+    pub secret_hash: String,
+}
+
+fn hash(secret: &str) -> String {
+    STANDARD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Compares hashes without stopping at the first difference.
+fn same_hash(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+/// A new prefix and secret. Version 4 UUIDs come from the operating system's secure random
+/// source; two of them give the secret 244 random bits.
+fn generate() -> (String, String) {
+    let prefix = format!("{KEY_PREFIX}{}", &Uuid::new_v4().simple().to_string()[..12]);
+    let secret = URL_SAFE_NO_PAD.encode([*Uuid::new_v4().as_bytes(), *Uuid::new_v4().as_bytes()].concat());
+    (prefix, secret)
+}
+
+/// The principal a plaintext `key` stands for, recording that it was used.
+pub async fn authenticate(keys: &dyn ApiKeyRepository, key: &str) -> Result<Principal, ApiError> {
+    let unknown = || ApiError::InvalidApiKey("it does not exist or has been revoked".to_string());
+    let (prefix, secret) = key.split_once('.').filter(|(prefix, _)| prefix.starts_with(KEY_PREFIX)).ok_or_else(unknown)?;
+    let stored = keys.find_key(prefix).await?.ok_or_else(unknown)?;
+    if !same_hash(&stored.secret_hash, &hash(secret)) {
+        return Err(unknown());
+    }
+    let now = Utc::now();
+    if stored.api_key.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(ApiError::InvalidApiKey("it has expired".to_string()));
+    }
+    let stale = stored.api_key.last_used_at.is_none_or(|last_used_at| {
+        (now - last_used_at).to_std().is_ok_and(|elapsed| elapsed >= LAST_USED_RESOLUTION)
+    });
+    if stale && let Err(error) = keys.touch_key(prefix, now.trunc_subsecs(6)).await {
+        // Losing a timestamp is no reason to turn the client away.
+        tracing::warn!(%error, prefix, "could not record API key use");
+    }
+    Ok(Principal {
+        subject: format!("api-key:{prefix}"),
+        businesses: [stored.business_id].into(),
+        scopes: stored.api_key.scopes.iter().map(|scope| scope.as_str().to_string()).collect(),
+    })
+}
+
+/// Create API key
+#[utoipa::path(
+    post,
+    path = "/business/{businessId}/api-keys",
+    request_body = CreateApiKey,
+    responses(
+        (status = 201, description = "API key created; the response is the only time its plaintext is shown", body = CreatedApiKey),
+        (status = 404, description = "Business not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Business is archived", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 415, description = "Body is not `application/json`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Fields are missing, unknown, have the wrong type or expire in the past", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business the key acts for"),
+    ),
+    security(("bearerAuth" = ["api-keys:write"]), ("apiKey" = ["api-keys:write"]))
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    BusinessPath(business_id): BusinessPath,
+    principal: Option<Principal>,
+    extract::Json(new_key): extract::Json<CreateApiKey>,
+) -> Result<impl IntoResponse, ApiError> {
+    // A key must not be a way to grant more than the caller holds.
+    if let Some(principal) = principal
+        && let Some(scope) = new_key.scopes.iter().find(|scope| !principal.scopes.contains(scope.as_str()))
+    {
+        return Err(ApiError::InsufficientScope(scope.as_str().to_string()));
+    }
+    businesses::active(&state, business_id).await?;
+    let created_at = Utc::now().trunc_subsecs(6);
+    if new_key.expires_at.is_some_and(|expires_at| expires_at <= created_at) {
+        return Err(ApiError::Validation([("expiresAt".to_string(), "must be in the future".to_string())].into()));
+    }
+    let mut scopes = new_key.scopes;
+    scopes.sort();
+    scopes.dedup();
+
+    for _ in 0..PREFIX_ATTEMPTS {
+        let (prefix, secret) = generate();
+        let api_key = ApiKey {
+            prefix: prefix.clone(),
+            name: new_key.name.clone(),
+            scopes: scopes.clone(),
+            created_at,
+            expires_at: new_key.expires_at,
+            last_used_at: None,
+        };
+        let stored = StoredApiKey { business_id, api_key: api_key.clone(), secret_hash: hash(&secret) };
+        match state.api_keys.create_key(stored).await {
+            // Prefixes are random, so a clash only needs another draw.
+            Err(RepositoryError::Conflict) => continue,
+            result => result?,
+        }
+        let created = CreatedApiKey { api_key, key: format!("{prefix}.{secret}") };
+        return Ok((StatusCode::CREATED, [(header::CACHE_CONTROL, "no-store")], Json(created)));
+    }
+    Err(ApiError::Internal(format!("no unused API key prefix in {PREFIX_ATTEMPTS} attempts").into()))
+}
+
+/// List API keys
+#[utoipa::path(
+    get,
+    path = "/business/{businessId}/api-keys",
+    responses(
+        (status = 200, description = "API keys of the business, including expired ones, ordered by prefix", body = Vec<ApiKey>),
+        (status = 404, description = "Business not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business the keys act for"),
+    ),
+    security(("bearerAuth" = ["api-keys:read"]), ("apiKey" = ["api-keys:read"]))
+)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    BusinessPath(business_id): BusinessPath,
+) -> Result<Json<Vec<ApiKey>>, ApiError> {
+    businesses::existing(&state, business_id).await?;
+    Ok(Json(state.api_keys.list_keys(business_id).await?))
+}
+
+/// Revoke API key
+#[utoipa::path(
+    delete,
+    path = "/business/{businessId}/api-keys/{prefix}",
+    responses(
+        (status = 204, description = "API key revoked"),
+        (status = 404, description = "API key or business not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Business is archived", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business the key acts for"),
+        ("prefix" = String, Path, description = "Prefix of the key to revoke"),
+    ),
+    security(("bearerAuth" = ["api-keys:write"]), ("apiKey" = ["api-keys:write"]))
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    ApiKeyPath(business_id, prefix): ApiKeyPath,
+) -> Result<StatusCode, ApiError> {
+    businesses::active(&state, business_id).await?;
+    match state.api_keys.delete_key(business_id, &prefix).await {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(RepositoryError::NotFound) => Err(ApiError::ApiKeyNotFound),
+        Err(error) => Err(error.into()),
+    }
+}
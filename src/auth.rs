@@ -0,0 +1,310 @@
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use axum::extract::{FromRequestParts, OptionalFromRequestParts, Request, State};
+use axum::http::header;
+use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::Response;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk, JwkSet, KeyAlgorithm, PublicKeyUse};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::api_keys::{self, API_KEY_HEADER};
+use crate::error::ApiError;
+use crate::lambda;
+use crate::AppState;
+
+/// Signing algorithms accepted; symmetric ones would let anyone holding the JWKS mint tokens.
+const ALGORITHMS: [Algorithm; 2] = [Algorithm::RS256, Algorithm::ES256];
+
+/// How long the JWKS is cached by default.
+pub const DEFAULT_JWKS_TTL: Duration = Duration::from_secs(300);
+
+/// Shortest time between fetches triggered by a token signed with a key not in the cached set,
+/// so that tokens with made-up key ids cannot hammer the identity provider.
+const MIN_REFRESH: Duration = Duration::from_secs(30);
+
+/// Where the JSON Web Key Set is read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JwksSource {
+    Url(String),
+    File(PathBuf),
+}
+
+impl FromStr for JwksSource {
+    type Err = String;
+
+    /// An `http://` or `https://` URL, otherwise a file path.
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        if source.is_empty() {
+            return Err("expected a URL or a file path".to_string());
+        }
+        if source.starts_with("https://") || source.starts_with("http://") {
+            Ok(Self::Url(source.to_string()))
+        } else {
+            Ok(Self::File(source.into()))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JwksSource {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Which bearer tokens are accepted.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Required `iss` claim.
+    pub issuer: String,
+    /// Required `aud` claim.
+    pub audience: String,
+    /// URL or file of the keys tokens are signed with.
+    pub jwks: JwksSource,
+    /// Seconds the keys are cached for.
+    #[serde(default = "default_jwks_ttl")]
+    pub jwks_ttl: u64,
+}
+
+fn default_jwks_ttl() -> u64 {
+    DEFAULT_JWKS_TTL.as_secs()
+}
+
+/// Claims of a verified token, available to handlers as an extractor.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub iss: String,
+    pub exp: u64,
+    /// Space-separated scopes granted to the token.
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// Businesses the subject is a member of.
+    #[serde(default)]
+    pub businesses: Vec<Uuid>,
+    /// Every other claim, including `aud`.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Claims {
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.iter().flat_map(|scope| scope.split_whitespace())
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Claims {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Claims>().cloned().ok_or(ApiError::MissingCredentials)
+    }
+}
+
+/// Who a request is made for, however it was authenticated; what [`crate::policy`] checks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Principal {
+    pub subject: String,
+    /// Businesses whose users the principal may act on.
+    pub businesses: BTreeSet<Uuid>,
+    /// Scopes such as `users:read` the principal was granted.
+    pub scopes: BTreeSet<String>,
+}
+
+impl From<&Claims> for Principal {
+    fn from(claims: &Claims) -> Self {
+        Self {
+            subject: claims.sub.clone(),
+            businesses: claims.businesses.iter().copied().collect(),
+            scopes: claims.scopes().map(str::to_string).collect(),
+        }
+    }
+}
+
+/// The principal [`authenticate`] found, or else the one an API Gateway authorizer attached
+/// to the Lambda event, so that handlers need not know which of them verified the caller.
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let principal = <Self as OptionalFromRequestParts<S>>::from_request_parts(parts, state).await;
+        principal.unwrap_or_default().ok_or(ApiError::MissingCredentials)
+    }
+}
+
+/// `None` for anonymous requests, which are only let through when [anonymous
+/// access](AppState::with_anonymous_access) is on.
+impl<S: Send + Sync> OptionalFromRequestParts<S> for Principal {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<Principal>().cloned().or_else(|| lambda::authorizer_principal(&parts.extensions)))
+    }
+}
+
+/// The JWKS, fetched on first use and again once it expires.
+struct KeySet {
+    source: JwksSource,
+    ttl: Duration,
+    client: reqwest::Client,
+    cached: RwLock<Option<(Instant, Arc<JwkSet>)>>,
+}
+
+impl KeySet {
+    async fn fetch(&self) -> Result<JwkSet, Box<dyn std::error::Error + Send + Sync>> {
+        let keys = match &self.source {
+            JwksSource::Url(url) => self.client.get(url).send().await?.error_for_status()?.json().await?,
+            JwksSource::File(path) => serde_json::from_str(&tokio::fs::read_to_string(path).await?)?,
+        };
+        Ok(keys)
+    }
+
+    /// The key a token names, refetching the set if it has expired or, now and then, if the
+    /// key is not in it, as happens when the identity provider rotates keys.
+    async fn key(&self, kid: Option<&str>, algorithm: Algorithm) -> Result<Jwk, ApiError> {
+        let cached = self.cached.read().unwrap().clone();
+        if let Some((fetched, keys)) = &cached {
+            let expired = fetched.elapsed() >= self.ttl;
+            match find(keys, kid, algorithm) {
+                Some(key) if !expired => return Ok(key),
+                None if !expired && fetched.elapsed() < MIN_REFRESH => {
+                    return Err(ApiError::InvalidToken("it is signed with an unknown key".to_string()));
+                }
+                _ => {}
+            }
+        }
+
+        let keys = match self.fetch().await {
+            Ok(keys) => Arc::new(keys),
+            Err(error) => {
+                // Keep accepting tokens signed with known keys while the provider is down.
+                tracing::warn!(%error, "could not fetch JWKS");
+                if let Some(key) = cached.and_then(|(_, keys)| find(&keys, kid, algorithm)) {
+                    return Ok(key);
+                }
+                return Err(ApiError::Internal(format!("could not fetch JWKS: {error}").into()));
+            }
+        };
+        *self.cached.write().unwrap() = Some((Instant::now(), keys.clone()));
+        find(&keys, kid, algorithm).ok_or_else(|| ApiError::InvalidToken("it is signed with an unknown key".to_string()))
+    }
+}
+
+/// The signing key in `keys` with id `kid`, or the only one for `algorithm` if the token
+/// names none.
+fn find(keys: &JwkSet, kid: Option<&str>, algorithm: Algorithm) -> Option<Jwk> {
+    let usable = |key: &&Jwk| {
+        key.common.public_key_use.as_ref().is_none_or(|key_use| *key_use == PublicKeyUse::Signature)
+            && key.common.key_algorithm.is_none_or(|key_algorithm| key_algorithm == KeyAlgorithm::from(algorithm))
+    };
+    match kid {
+        Some(kid) => keys.find(kid).filter(usable).cloned(),
+        None => {
+            let mut candidates = keys.keys.iter().filter(usable).filter(|key| match algorithm {
+                Algorithm::ES256 => matches!(key.algorithm, AlgorithmParameters::EllipticCurve(_)),
+                _ => matches!(key.algorithm, AlgorithmParameters::RSA(_)),
+            });
+            match (candidates.next(), candidates.next()) {
+                (Some(key), None) => Some(key.clone()),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Why a token failed verification, worded for the problem's `detail`.
+fn rejection(error: jsonwebtoken::errors::Error) -> ApiError {
+    let reason = match error.kind() {
+        ErrorKind::ExpiredSignature => "it has expired",
+        ErrorKind::ImmatureSignature => "it is not valid yet",
+        ErrorKind::InvalidIssuer => "it was not issued by the expected issuer",
+        ErrorKind::InvalidAudience => "it is not meant for this API",
+        ErrorKind::InvalidSignature => "its signature does not match",
+        ErrorKind::MissingRequiredClaim(claim) => return ApiError::InvalidToken(format!("it has no `{claim}` claim")),
+        _ => "it is malformed",
+    };
+    ApiError::InvalidToken(reason.to_string())
+}
+
+/// Verifies bearer tokens against the configured issuer, audience and keys.
+pub struct Authenticator {
+    issuer: String,
+    audience: String,
+    keys: KeySet,
+}
+
+impl Authenticator {
+    pub fn new(config: &AuthConfig) -> Self {
+        Self {
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+            keys: KeySet {
+                source: config.jwks.clone(),
+                ttl: Duration::from_secs(config.jwks_ttl),
+                client: reqwest::Client::new(),
+                cached: RwLock::default(),
+            },
+        }
+    }
+
+    /// Checks the token's signature, `exp`, `iss` and `aud`, returning its claims.
+    pub async fn verify(&self, token: &str) -> Result<Claims, ApiError> {
+        let header = jsonwebtoken::decode_header(token).map_err(rejection)?;
+        if !ALGORITHMS.contains(&header.alg) {
+            return Err(ApiError::InvalidToken(format!("{:?} signatures are not accepted", header.alg)));
+        }
+        let jwk = self.keys.key(header.kid.as_deref(), header.alg).await?;
+        let key = DecodingKey::from_jwk(&jwk).map_err(|error| ApiError::Internal(error.into()))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        jsonwebtoken::decode::<Claims>(token, &key, &validation).map(|data| data.claims).map_err(rejection)
+    }
+}
+
+/// Middleware that makes the [`Principal`], and for a token its [`Claims`], available to
+/// handlers. An identity from an API Gateway authorizer is trusted as it is, since only the
+/// Lambda event can carry one; otherwise the request needs a valid API key or bearer token,
+/// unless [anonymous access](AppState::with_anonymous_access) lets requests without either through.
+pub async fn authenticate(State(state): State<AppState>, mut request: Request, next: Next) -> Result<Response, ApiError> {
+    if let Some(principal) = lambda::authorizer_principal(request.extensions()) {
+        request.extensions_mut().insert(principal);
+        return Ok(next.run(request).await);
+    }
+    let headers = request.headers();
+    let api_key = headers.get(API_KEY_HEADER).map(|key| key.to_str().unwrap_or_default().to_string());
+    let authorization = headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+    let token = authorization
+        .and_then(|value| value.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim().to_string());
+
+    let principal = match (api_key, token, &state.auth) {
+        (Some(key), _, _) => api_keys::authenticate(state.api_keys.as_ref(), &key).await?,
+        (None, Some(token), Some(authenticator)) => {
+            let claims = authenticator.verify(&token).await?;
+            let principal = Principal::from(&claims);
+            request.extensions_mut().insert(claims);
+            principal
+        }
+        (None, Some(_), None) => {
+            return Err(ApiError::InvalidToken("bearer tokens are not accepted by this server".to_string()));
+        }
+        (None, None, _) if state.anonymous => return Ok(next.run(request).await),
+        (None, None, _) => return Err(ApiError::MissingCredentials),
+    };
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,440 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::State, response::IntoResponse, http::{header, StatusCode}, Json};
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::auth::Principal;
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{self, BusinessPath, Query};
+use crate::patch::{merge, MergePatch};
+use crate::repository::RepositoryError;
+use crate::validation;
+use crate::AppState;
+
+/// Business Status
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+/// Whether a business is in use.
+pub enum BusinessStatus {
+    /// In use.
+    #[default]
+    Active,
+    /// No longer in use; its users and keys are kept, and it can be restored.
+    Archived,
+}
+
+impl BusinessStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Archived => "archived",
+        }
+    }
+}
+
+/// Business Settings
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default, rename_all = "camelCase")]
+/// Preferences that apply across the business.
+pub struct BusinessSettings {
+    #[schema(
+        example = "Europe/London",
+        max_length = 64,
+        pattern = r"^[A-Za-z]+(?:[/_+-][A-Za-z0-9]+)*$",
+    )]
+    /// IANA time zone the business works in. Defaults to `UTC`.
+    pub time_zone: String,
+    #[schema(
+        example = "en-GB",
+        max_length = 35,
+        pattern = r"^[a-z]{2,3}(?:-[A-Za-z0-9]{2,8})*$",
+    )]
+    /// Language tag for messages and formatting. Defaults to `en`.
+    pub locale: String,
+}
+
+impl Default for BusinessSettings {
+    fn default() -> Self {
+        Self { time_zone: "UTC".to_string(), locale: "en".to_string() }
+    }
+}
+
+/// Business
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// An organisation; users and API keys live under `/business/{businessId}`.
+pub struct Business {
+    #[schema(
+        example = "7c9e6679-7425-40de-944b-e07fc1f90ae7",
+    )]
+    /// Unique identifier for the business.
+    pub uuid: Uuid,
+    #[schema(
+        example = "Acme Ltd",
+    )]
+    /// Name of the business.
+    pub name: String,
+    #[schema(
+        example = "acme-ltd",
+    )]
+    /// Short name for URLs, unique across businesses.
+    pub slug: String,
+    #[schema(
+        inline,
+        read_only,
+    )]
+    /// Whether the business is in use; changed by archiving and restoring it.
+    pub status: BusinessStatus,
+    /// Preferences that apply across the business.
+    pub settings: BusinessSettings,
+    #[schema(
+        read_only,
+        example = "2025-01-01T09:30:00Z",
+    )]
+    /// When the business was created.
+    pub created_at: DateTime<Utc>,
+    #[schema(
+        read_only,
+        example = "2025-01-01T09:30:00Z",
+    )]
+    /// When the business was last changed, archived or restored.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Business {
+    /// A business for an id that users or API keys were stored under before businesses were
+    /// records of their own, named after its id.
+    pub fn placeholder(business_id: Uuid, created_at: DateTime<Utc>) -> Self {
+        Self {
+            uuid: business_id,
+            name: business_id.to_string(),
+            slug: business_id.to_string(),
+            status: BusinessStatus::Active,
+            settings: BusinessSettings::default(),
+            created_at,
+            updated_at: created_at,
+        }
+    }
+}
+
+/// New Business
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Details of a business to create; the server assigns its uuid.
+pub struct CreateBusiness {
+    #[schema(
+        example = "Acme Ltd",
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// Name of the business.
+    pub name: String,
+    #[schema(
+        example = "acme-ltd",
+        min_length = 1,
+        max_length = 63,
+        pattern = r"^[a-z0-9]+(?:-[a-z0-9]+)*$",
+    )]
+    /// Short name for URLs, unique across businesses.
+    pub slug: String,
+    #[serde(default)]
+    #[schema(
+        inline,
+    )]
+    /// Preferences that apply across the business; omitted ones take their defaults.
+    pub settings: BusinessSettings,
+}
+
+/// Editable Business
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Every field of a business a client may change; the uuid is immutable and the status
+/// changes by archiving and restoring.
+pub struct UpdateBusiness {
+    #[schema(
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// Name of the business.
+    pub name: String,
+    #[schema(
+        min_length = 1,
+        max_length = 63,
+        pattern = r"^[a-z0-9]+(?:-[a-z0-9]+)*$",
+    )]
+    /// Short name for URLs, unique across businesses.
+    pub slug: String,
+    #[schema(
+        inline,
+    )]
+    /// Preferences that apply across the business.
+    pub settings: BusinessSettings,
+}
+
+impl From<Business> for UpdateBusiness {
+    fn from(business: Business) -> Self {
+        Self { name: business.name, slug: business.slug, settings: business.settings }
+    }
+}
+
+/// Business Settings Patch
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Changes to business settings; omitted settings are left unchanged.
+pub struct BusinessSettingsPatch {
+    #[schema(
+        example = "Europe/Paris",
+        max_length = 64,
+        pattern = r"^[A-Za-z]+(?:[/_+-][A-Za-z0-9]+)*$",
+    )]
+    /// IANA time zone the business works in.
+    pub time_zone: Option<String>,
+    #[schema(
+        max_length = 35,
+        pattern = r"^[a-z]{2,3}(?:-[A-Za-z0-9]{2,8})*$",
+    )]
+    /// Language tag for messages and formatting.
+    pub locale: Option<String>,
+}
+
+/// Business Patch
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// JSON Merge Patch (RFC 7396) of a business; omitted fields are left unchanged.
+pub struct BusinessPatch {
+    #[schema(
+        example = "Acme Group",
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// Name of the business.
+    pub name: Option<String>,
+    #[schema(
+        min_length = 1,
+        max_length = 63,
+        pattern = r"^[a-z0-9]+(?:-[a-z0-9]+)*$",
+    )]
+    /// Short name for URLs, unique across businesses.
+    pub slug: Option<String>,
+    /// Preferences that apply across the business.
+    pub settings: Option<BusinessSettingsPatch>,
+}
+
+/// Looks up a business, which must exist for anything under it to be reached.
+pub async fn existing(state: &AppState, business_id: Uuid) -> Result<Business, ApiError> {
+    state.businesses.get_business(business_id).await?.ok_or(ApiError::BusinessNotFound)
+}
+
+/// Looks up a business that is not archived, for changing it or anything under it.
+pub async fn active(state: &AppState, business_id: Uuid) -> Result<Business, ApiError> {
+    let business = existing(state, business_id).await?;
+    if business.status == BusinessStatus::Archived {
+        return Err(ApiError::BusinessArchived);
+    }
+    Ok(business)
+}
+
+/// Stores a changed business, stamping when it changed.
+async fn save(state: &AppState, mut business: Business) -> Result<Business, ApiError> {
+    business.updated_at = Utc::now().trunc_subsecs(6);
+    match state.businesses.update_business(business).await {
+        Err(RepositoryError::NotFound) => Err(ApiError::BusinessNotFound),
+        result => Ok(result?),
+    }
+}
+
+/// Create business
+#[utoipa::path(
+    post,
+    path = "/business",
+    request_body = CreateBusiness,
+    responses(
+        (status = 201, description = "Business created", body = Business,
+            headers(("Location" = String, description = "Path of the created business"))),
+        (status = 409, description = "Slug already in use by another business", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 415, description = "Body is not `application/json`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Fields are missing, unknown or have the wrong type", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearerAuth" = ["businesses:write"]), ("apiKey" = ["businesses:write"]))
+)]
+pub async fn create_business(
+    State(state): State<AppState>,
+    extract::Json(new_business): extract::Json<CreateBusiness>,
+) -> Result<impl IntoResponse, ApiError> {
+    let now = Utc::now().trunc_subsecs(6);
+    let business = Business {
+        uuid: Uuid::new_v4(),
+        name: new_business.name,
+        slug: new_business.slug,
+        status: BusinessStatus::Active,
+        settings: new_business.settings,
+        created_at: now,
+        updated_at: now,
+    };
+    let business = match state.businesses.create_business(business).await {
+        Err(RepositoryError::Conflict) => return Err(ApiError::BusinessExists),
+        result => result?,
+    };
+    let location = format!("/business/{}", business.uuid);
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(business)))
+}
+
+/// Filters for listing businesses.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListBusinessesQuery {
+    #[param(
+        inline,
+    )]
+    /// Only businesses with this status.
+    pub status: Option<BusinessStatus>,
+}
+
+/// List businesses
+///
+/// Only the businesses the caller is a member of are listed.
+#[utoipa::path(
+    get,
+    path = "/business",
+    responses(
+        (status = 200, description = "Businesses, ordered by uuid", body = Vec<Business>),
+        (status = 400, description = "Malformed status", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ListBusinessesQuery,
+    ),
+    security(("bearerAuth" = ["businesses:read"]), ("apiKey" = ["businesses:read"]))
+)]
+pub async fn list_businesses(
+    State(state): State<AppState>,
+    principal: Option<Principal>,
+    Query(query): Query<ListBusinessesQuery>,
+) -> Result<Json<Vec<Business>>, ApiError> {
+    let businesses = state.businesses.list_businesses().await?;
+    Ok(Json(
+        businesses
+            .into_iter()
+            .filter(|business| principal.as_ref().is_none_or(|principal| principal.businesses.contains(&business.uuid)))
+            .filter(|business| query.status.is_none_or(|status| business.status == status))
+            .collect(),
+    ))
+}
+
+/// Get business by id
+#[utoipa::path(
+    get,
+    path = "/business/{businessId}",
+    responses(
+        (status = 200, description = "Business", body = Business),
+        (status = 404, description = "Business not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id to get"),
+    ),
+    security(("bearerAuth" = ["businesses:read"]), ("apiKey" = ["businesses:read"]))
+)]
+pub async fn get_business(
+    State(state): State<AppState>,
+    BusinessPath(business_id): BusinessPath,
+) -> Result<Json<Business>, ApiError> {
+    Ok(Json(existing(&state, business_id).await?))
+}
+
+/// Update business fields
+#[utoipa::path(
+    patch,
+    path = "/business/{businessId}",
+    request_body(content = BusinessPatch, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "Business", body = Business),
+        (status = 404, description = "Business not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Slug already in use by another business, or the business is archived", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 415, description = "Body is not `application/merge-patch+json`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Fields are unknown, have the wrong type, are removed or change the uuid", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id to update"),
+    ),
+    security(("bearerAuth" = ["businesses:write"]), ("apiKey" = ["businesses:write"]))
+)]
+pub async fn update_business(
+    State(state): State<AppState>,
+    BusinessPath(business_id): BusinessPath,
+    MergePatch(mut patch): MergePatch,
+) -> Result<Json<Business>, ApiError> {
+    let current = active(&state, business_id).await?;
+
+    let mut errors = BTreeMap::new();
+    validation::take_uuid(&mut patch, business_id, &mut errors);
+    let mut document = json!(UpdateBusiness::from(current.clone()));
+    merge(&mut document, &Value::Object(patch));
+    let Value::Object(document) = document else {
+        unreachable!("merging an object patch yields an object");
+    };
+    errors.extend(validation::check::<UpdateBusiness>(&document));
+    if !errors.is_empty() {
+        return Err(ApiError::Validation(errors));
+    }
+    let update: UpdateBusiness =
+        serde_json::from_value(Value::Object(document)).map_err(|error| ApiError::Unprocessable(error.to_string()))?;
+    let business = Business { name: update.name, slug: update.slug, settings: update.settings, ..current };
+    Ok(Json(save(&state, business).await?))
+}
+
+/// Archive business
+///
+/// Archived businesses keep their users and API keys, which can all still be read, but neither
+/// the business nor anything under it can be changed until it is restored.
+#[utoipa::path(
+    post,
+    path = "/business/{businessId}/archive",
+    responses(
+        (status = 200, description = "Business", body = Business),
+        (status = 404, description = "Business not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id to archive"),
+    ),
+    security(("bearerAuth" = ["businesses:write"]), ("apiKey" = ["businesses:write"]))
+)]
+pub async fn archive_business(
+    State(state): State<AppState>,
+    BusinessPath(business_id): BusinessPath,
+) -> Result<Json<Business>, ApiError> {
+    let business = existing(&state, business_id).await?;
+    if business.status == BusinessStatus::Archived {
+        return Ok(Json(business));
+    }
+    Ok(Json(save(&state, Business { status: BusinessStatus::Archived, ..business }).await?))
+}
+
+/// Restore archived business
+#[utoipa::path(
+    post,
+    path = "/business/{businessId}/restore",
+    responses(
+        (status = 200, description = "Business", body = Business),
+        (status = 404, description = "Business not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id to restore"),
+    ),
+    security(("bearerAuth" = ["businesses:write"]), ("apiKey" = ["businesses:write"]))
+)]
+pub async fn restore_business(
+    State(state): State<AppState>,
+    BusinessPath(business_id): BusinessPath,
+) -> Result<Json<Business>, ApiError> {
+    let business = existing(&state, business_id).await?;
+    if business.status == BusinessStatus::Active {
+        return Ok(Json(business));
+    }
+    Ok(Json(save(&state, Business { status: BusinessStatus::Active, ..business }).await?))
+}
@@ -0,0 +1,348 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+/// HTTP methods an OpenAPI path item can describe, in the order changes are reported.
+const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Deepest schema nesting compared, which also stops recursive schemas.
+const MAX_DEPTH: usize = 32;
+
+/// A difference between a baseline OpenAPI document and the current one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Whether a client written against the baseline may fail against the current document.
+    pub breaking: bool,
+    /// Where the change is, such as `GET /business/{businessId}/users response 200.items[].email`.
+    pub location: String,
+    pub description: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.description)
+    }
+}
+
+/// Which way a schema's values travel. A client's requests break when the server accepts
+/// less than before; its response handling breaks when the server may send more.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Request,
+    Response,
+}
+
+/// Reads an OpenAPI document in JSON or YAML.
+pub fn parse(document: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    if document.trim_start().starts_with('{') {
+        Ok(serde_json::from_str(document)?)
+    } else {
+        Ok(serde_yaml::from_str(document)?)
+    }
+}
+
+/// Lists every change from `baseline` to `current`, classified as breaking or not.
+pub fn diff(baseline: &Value, current: &Value) -> Vec<Change> {
+    let mut diff = Diff { baseline, current, changes: Vec::new() };
+    diff.paths();
+    diff.changes
+}
+
+struct Diff<'a> {
+    baseline: &'a Value,
+    current: &'a Value,
+    changes: Vec<Change>,
+}
+
+fn object(value: &Value, key: &str) -> Map<String, Value> {
+    value.get(key).and_then(Value::as_object).cloned().unwrap_or_default()
+}
+
+fn is_required(value: &Value) -> bool {
+    value.get("required").and_then(Value::as_bool).unwrap_or(false)
+}
+
+/// The names listed in a schema's `required`.
+fn required_fields(schema: &Value) -> BTreeSet<String> {
+    let names = schema.get("required").and_then(Value::as_array).into_iter().flatten();
+    names.filter_map(Value::as_str).map(str::to_string).collect()
+}
+
+/// The types a schema allows, or `None` if it allows any.
+fn types(schema: &Value) -> Option<BTreeSet<String>> {
+    match schema.get("type")? {
+        Value::String(schema_type) => Some(BTreeSet::from([schema_type.clone()])),
+        Value::Array(types) => Some(types.iter().filter_map(Value::as_str).map(str::to_string).collect()),
+        _ => None,
+    }
+}
+
+/// Follows a local `$ref` within the document `value` came from.
+fn resolve<'v>(document: &'v Value, mut value: &'v Value) -> &'v Value {
+    for _ in 0..MAX_DEPTH {
+        let pointer = value.get("$ref").and_then(Value::as_str).and_then(|target| target.strip_prefix('#'));
+        match pointer.and_then(|pointer| document.pointer(pointer)) {
+            Some(target) => value = target,
+            None => break,
+        }
+    }
+    value
+}
+
+fn listed(values: &BTreeSet<String>) -> String {
+    values.iter().map(|value| format!("`{value}`")).collect::<Vec<_>>().join(", ")
+}
+
+impl Diff<'_> {
+    fn change(&mut self, breaking: bool, location: impl Into<String>, description: impl Into<String>) {
+        self.changes.push(Change { breaking, location: location.into(), description: description.into() });
+    }
+
+    fn paths(&mut self) {
+        let (old_paths, new_paths) = (object(self.baseline, "paths"), object(self.current, "paths"));
+        for (path, old_item) in &old_paths {
+            let Some(new_item) = new_paths.get(path) else {
+                self.change(true, path, "path removed");
+                continue;
+            };
+            for method in METHODS {
+                let location = format!("{} {path}", method.to_uppercase());
+                match (old_item.get(method), new_item.get(method)) {
+                    (Some(_), None) => self.change(true, location, "operation removed"),
+                    (None, Some(_)) => self.change(false, location, "operation added"),
+                    (Some(old), Some(new)) => self.operation(&location, old, new),
+                    (None, None) => {}
+                }
+            }
+        }
+        for path in new_paths.keys().filter(|path| !old_paths.contains_key(*path)) {
+            self.change(false, path, "path added");
+        }
+    }
+
+    fn parameters(document: &Value, operation: &Value) -> Vec<(String, Value)> {
+        let parameters = operation.get("parameters").and_then(Value::as_array).into_iter().flatten();
+        parameters
+            .map(|parameter| resolve(document, parameter).clone())
+            .map(|parameter| {
+                let place = parameter.get("in").and_then(Value::as_str).unwrap_or_default();
+                let name = parameter.get("name").and_then(Value::as_str).unwrap_or_default();
+                (format!("{place} {name}"), parameter)
+            })
+            .collect()
+    }
+
+    fn operation(&mut self, location: &str, old: &Value, new: &Value) {
+        let old_parameters = Self::parameters(self.baseline, old);
+        let new_parameters = Self::parameters(self.current, new);
+        for (key, old_parameter) in &old_parameters {
+            let location = format!("{location} parameter {key}");
+            match new_parameters.iter().find(|(new_key, _)| new_key == key) {
+                None => self.change(true, location, "parameter removed"),
+                Some((_, new_parameter)) => {
+                    if !is_required(old_parameter) && is_required(new_parameter) {
+                        self.change(true, &location, "parameter became required");
+                    }
+                    if let (Some(old_schema), Some(new_schema)) = (old_parameter.get("schema"), new_parameter.get("schema")) {
+                        self.schema(&location, old_schema, new_schema, Direction::Request, 0);
+                    }
+                }
+            }
+        }
+        for (key, new_parameter) in &new_parameters {
+            if !old_parameters.iter().any(|(old_key, _)| old_key == key) {
+                let location = format!("{location} parameter {key}");
+                if is_required(new_parameter) {
+                    self.change(true, location, "required parameter added");
+                } else {
+                    self.change(false, location, "optional parameter added");
+                }
+            }
+        }
+
+        self.request_body(&format!("{location} request"), old.get("requestBody"), new.get("requestBody"));
+
+        let (old_responses, new_responses) = (object(old, "responses"), object(new, "responses"));
+        for (status, old_response) in &old_responses {
+            let location = format!("{location} response {status}");
+            match new_responses.get(status) {
+                // Clients only depend on the responses they were told to expect success from.
+                None => self.change(status.starts_with('2'), location, "response removed"),
+                Some(new_response) => {
+                    let old_response = resolve(self.baseline, old_response).clone();
+                    let new_response = resolve(self.current, new_response).clone();
+                    self.content(&location, &old_response, &new_response, Direction::Response);
+                }
+            }
+        }
+        for status in new_responses.keys().filter(|status| !old_responses.contains_key(*status)) {
+            self.change(false, format!("{location} response {status}"), "response added");
+        }
+    }
+
+    fn request_body(&mut self, location: &str, old: Option<&Value>, new: Option<&Value>) {
+        match (old, new) {
+            (None, None) => {}
+            (Some(_), None) => self.change(true, location, "request body removed"),
+            (None, Some(new)) => {
+                let required = is_required(resolve(self.current, new));
+                self.change(required, location, format!("{} request body added", if required { "required" } else { "optional" }));
+            }
+            (Some(old), Some(new)) => {
+                let old = resolve(self.baseline, old).clone();
+                let new = resolve(self.current, new).clone();
+                if !is_required(&old) && is_required(&new) {
+                    self.change(true, location, "request body became required");
+                }
+                self.content(location, &old, &new, Direction::Request);
+            }
+        }
+    }
+
+    /// Compares the media types of a request body or response.
+    fn content(&mut self, location: &str, old: &Value, new: &Value, direction: Direction) {
+        let (old_content, new_content) = (object(old, "content"), object(new, "content"));
+        for (media_type, old_media) in &old_content {
+            match new_content.get(media_type) {
+                None => self.change(true, location, format!("`{media_type}` no longer supported")),
+                Some(new_media) => {
+                    if let (Some(old_schema), Some(new_schema)) = (old_media.get("schema"), new_media.get("schema")) {
+                        self.schema(location, old_schema, new_schema, direction, 0);
+                    }
+                }
+            }
+        }
+        for media_type in new_content.keys().filter(|media_type| !old_content.contains_key(*media_type)) {
+            self.change(false, location, format!("`{media_type}` now supported"));
+        }
+    }
+
+    fn schema(&mut self, location: &str, old: &Value, new: &Value, direction: Direction, depth: usize) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let old = resolve(self.baseline, old).clone();
+        let new = resolve(self.current, new).clone();
+        let request = direction == Direction::Request;
+
+        match (types(&old), types(&new)) {
+            (Some(old_types), Some(new_types)) => {
+                let removed: BTreeSet<_> = old_types.difference(&new_types).cloned().collect();
+                let added: BTreeSet<_> = new_types.difference(&old_types).cloned().collect();
+                if !removed.is_empty() {
+                    let description = match direction {
+                        Direction::Request => format!("type narrowed, no longer accepts {}", listed(&removed)),
+                        Direction::Response => format!("type narrowed, no longer returns {}", listed(&removed)),
+                    };
+                    self.change(request, location, description);
+                }
+                if !added.is_empty() {
+                    let description = match direction {
+                        Direction::Request => format!("type widened, now accepts {}", listed(&added)),
+                        Direction::Response => format!("type widened, may now return {}", listed(&added)),
+                    };
+                    self.change(!request, location, description);
+                }
+            }
+            (None, Some(new_types)) => self.change(request, location, format!("type narrowed to {}", listed(&new_types))),
+            (Some(_), None) => self.change(!request, location, "type widened to any value"),
+            (None, None) => {}
+        }
+
+        self.enumeration(location, &old, &new, direction);
+        if request {
+            self.constraints(location, &old, &new);
+        }
+        self.properties(location, &old, &new, direction, depth);
+
+        if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+            self.schema(&format!("{location}[]"), old_items, new_items, direction, depth + 1);
+        }
+        for keyword in ["oneOf", "anyOf", "allOf"] {
+            let (Some(old_variants), Some(new_variants)) =
+                (old.get(keyword).and_then(Value::as_array), new.get(keyword).and_then(Value::as_array))
+            else {
+                continue;
+            };
+            if old_variants.len() != new_variants.len() {
+                // More `allOf` parts narrow a schema; more `oneOf`/`anyOf` variants widen it.
+                let widened = (new_variants.len() > old_variants.len()) != (keyword == "allOf");
+                self.change(widened != request, location, format!("`{keyword}` variants changed"));
+                continue;
+            }
+            for (index, (old_variant, new_variant)) in old_variants.iter().zip(new_variants).enumerate() {
+                self.schema(&format!("{location}<{keyword}[{index}]>"), old_variant, new_variant, direction, depth + 1);
+            }
+        }
+    }
+
+    fn enumeration(&mut self, location: &str, old: &Value, new: &Value, direction: Direction) {
+        let values = |schema: &Value| -> Option<BTreeSet<String>> {
+            Some(schema.get("enum")?.as_array()?.iter().map(Value::to_string).collect())
+        };
+        let request = direction == Direction::Request;
+        match (values(old), values(new)) {
+            (Some(old_values), Some(new_values)) => {
+                let removed: BTreeSet<_> = old_values.difference(&new_values).cloned().collect();
+                let added: BTreeSet<_> = new_values.difference(&old_values).cloned().collect();
+                if !removed.is_empty() {
+                    self.change(request, location, format!("enum values removed: {}", removed.into_iter().collect::<Vec<_>>().join(", ")));
+                }
+                if !added.is_empty() {
+                    self.change(!request, location, format!("enum values added: {}", added.into_iter().collect::<Vec<_>>().join(", ")));
+                }
+            }
+            (None, Some(_)) => self.change(request, location, "restricted to an enum"),
+            (Some(_), None) => self.change(!request, location, "no longer restricted to an enum"),
+            (None, None) => {}
+        }
+    }
+
+    /// Compares the limits on values a request may send.
+    fn constraints(&mut self, location: &str, old: &Value, new: &Value) {
+        let number = |schema: &Value, keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+        for (keyword, lower_bound) in [("minLength", true), ("maxLength", false), ("minimum", true), ("maximum", false), ("minItems", true), ("maxItems", false)] {
+            let narrowed = match (number(old, keyword), number(new, keyword)) {
+                (old, new) if old == new => continue,
+                (None, Some(_)) => true,
+                (Some(_), None) => false,
+                (Some(old), Some(new)) => (new > old) == lower_bound,
+                (None, None) => continue,
+            };
+            let verb = if narrowed { "tightened" } else { "relaxed" };
+            self.change(narrowed, location, format!("`{keyword}` {verb}"));
+        }
+        for keyword in ["pattern", "format"] {
+            match (old.get(keyword), new.get(keyword)) {
+                (old, new) if old == new => {}
+                (_, None) => self.change(false, location, format!("`{keyword}` removed")),
+                (_, Some(new)) => self.change(true, location, format!("`{keyword}` changed to {new}")),
+            }
+        }
+    }
+
+    fn properties(&mut self, location: &str, old: &Value, new: &Value, direction: Direction, depth: usize) {
+        let (old_properties, new_properties) = (object(old, "properties"), object(new, "properties"));
+        let (old_required, new_required) = (required_fields(old), required_fields(new));
+        let request = direction == Direction::Request;
+        for (name, old_property) in &old_properties {
+            let location = format!("{location}.{name}");
+            let Some(new_property) = new_properties.get(name) else {
+                self.change(true, location, "field removed");
+                continue;
+            };
+            match (old_required.contains(name), new_required.contains(name)) {
+                (false, true) => self.change(request, &location, "field became required"),
+                (true, false) => self.change(!request, &location, "field became optional"),
+                _ => {}
+            }
+            self.schema(&location, old_property, new_property, direction, depth + 1);
+        }
+        for name in new_properties.keys().filter(|name| !old_properties.contains_key(*name)) {
+            let required = request && new_required.contains(name);
+            let description = if required { "required field added" } else { "field added" };
+            self.change(required, format!("{location}.{name}"), description);
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::auth::{AuthConfig, JwksSource};
+use crate::docs::{DocsConfig, SCALAR_THEMES};
+
+/// Where users are stored.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Storage {
+    /// In memory, seeded from a JSON file of users if one is given.
+    Memory {
+        #[serde(default)]
+        fixture: Option<PathBuf>,
+    },
+    /// SQLite at a `sqlite:` URL; migrations run on startup.
+    Sqlite { url: String },
+    /// A DynamoDB table, optionally at a local endpoint such as DynamoDB Local.
+    #[serde(rename = "dynamodb")]
+    DynamoDb {
+        table: String,
+        #[serde(default)]
+        endpoint: Option<String>,
+    },
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self::Memory { fixture: None }
+    }
+}
+
+/// Settings for the server, read from an optional TOML file and then the environment,
+/// which takes precedence.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Address to listen on when not running on Lambda; `0.0.0.0` in a container.
+    pub host: IpAddr,
+    pub port: u16,
+    /// Most verbose log level written: `off`, `error`, `warn`, `info`, `debug` or `trace`.
+    #[serde(deserialize_with = "parsed")]
+    pub log_level: LevelFilter,
+    pub docs: DocsConfig,
+    pub storage: Storage,
+    /// Bearer token verification; without it, only API keys and API Gateway authorizer
+    /// identities are accepted.
+    pub auth: Option<AuthConfig>,
+    /// Lets requests without an API key or API Gateway authorizer identity through as
+    /// anonymous, skipping every membership and scope check. For local development only.
+    pub auth_disabled: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 8080,
+            log_level: LevelFilter::INFO,
+            docs: DocsConfig::default(),
+            storage: Storage::default(),
+            auth: None,
+            auth_disabled: false,
+        }
+    }
+}
+
+/// Why the configuration could not be loaded, worded for someone starting the server.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("could not read config file {}: {source}", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("invalid config file {}: {source}", path.display())]
+    Parse { path: PathBuf, source: toml::de::Error },
+    #[error("invalid {key} `{value}`: {reason}")]
+    Invalid { key: String, value: String, reason: String },
+    #[error("missing {key}: {reason}")]
+    Missing { key: String, reason: String },
+}
+
+fn parsed<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" => Ok(true),
+        "false" | "0" | "no" => Ok(false),
+        _ => Err("expected `true` or `false`".to_string()),
+    }
+}
+
+/// Reads the environment variable `key` with `parse`, if it is set.
+fn env_value<T, E: Display>(
+    env: &impl Fn(&str) -> Option<String>,
+    key: &str,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<Option<T>, ConfigError> {
+    let Some(value) = env(key) else {
+        return Ok(None);
+    };
+    match parse(&value) {
+        Ok(parsed) => Ok(Some(parsed)),
+        Err(error) => Err(ConfigError::Invalid { key: key.to_string(), value, reason: error.to_string() }),
+    }
+}
+
+impl Config {
+    /// Loads the configuration from `file`, or the file named by `CONFIG_FILE`, and the
+    /// process environment.
+    pub fn load(file: Option<&Path>) -> Result<Self, ConfigError> {
+        let env = |key: &str| std::env::var(key).ok();
+        let file = file.map(Path::to_path_buf).or_else(|| env("CONFIG_FILE").map(PathBuf::from));
+        let contents = match &file {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .map_err(|source| ConfigError::Read { path: path.clone(), source })?;
+                Some((path.as_path(), contents))
+            }
+            None => None,
+        };
+        Self::from_sources(contents.as_ref().map(|(path, contents)| (*path, contents.as_str())), env)
+    }
+
+    /// Builds the configuration from a TOML `file` and its path, then `env`.
+    pub fn from_sources(file: Option<(&Path, &str)>, env: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let mut config = match file {
+            Some((path, contents)) => {
+                toml::from_str(contents).map_err(|source| ConfigError::Parse { path: path.to_path_buf(), source })?
+            }
+            None => Self::default(),
+        };
+
+        if let Some(host) = env_value(&env, "HOST", IpAddr::from_str)? {
+            config.host = host;
+        }
+        if let Some(port) = env_value(&env, "PORT", u16::from_str)? {
+            config.port = port;
+        }
+        if let Some(log_level) = env_value(&env, "LOG_LEVEL", LevelFilter::from_str)? {
+            config.log_level = log_level;
+        }
+        if let Some(enabled) = env_value(&env, "DOCS_ENABLED", parse_bool)? {
+            config.docs.enabled = enabled;
+        }
+        if let Some(path) = env("DOCS_PATH") {
+            config.docs.path = path;
+        }
+        if let Some(theme) = env("SCALAR_THEME") {
+            config.docs.theme = theme;
+        }
+        if let Some(scalar) = env_value(&env, "SCALAR_SOURCE", str::parse)? {
+            config.docs.scalar = scalar;
+        }
+
+        // Any of the storage variables replaces the file's storage section as a whole.
+        if let Some(table) = env("DYNAMODB_TABLE") {
+            config.storage = Storage::DynamoDb { table, endpoint: env("DYNAMODB_ENDPOINT") };
+        } else if let Some(url) = env("DATABASE_URL") {
+            config.storage = Storage::Sqlite { url };
+        } else if let Some(fixture) = env("USERS_FIXTURE") {
+            config.storage = Storage::Memory { fixture: Some(fixture.into()) };
+        }
+
+        config.auth = Self::auth(config.auth.take(), &env)?;
+        if let Some(disabled) = env_value(&env, "AUTH_DISABLED", parse_bool)? {
+            config.auth_disabled = disabled;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Overlays `AUTH_ISSUER`, `AUTH_AUDIENCE`, `AUTH_JWKS` and `AUTH_JWKS_TTL` on the file's
+    /// `[auth]` section; together they must name an issuer, an audience and keys.
+    fn auth(file: Option<AuthConfig>, env: &impl Fn(&str) -> Option<String>) -> Result<Option<AuthConfig>, ConfigError> {
+        let issuer = env("AUTH_ISSUER");
+        let audience = env("AUTH_AUDIENCE");
+        let jwks = env_value(env, "AUTH_JWKS", JwksSource::from_str)?;
+        let jwks_ttl = env_value(env, "AUTH_JWKS_TTL", u64::from_str)?;
+        if file.is_none() && issuer.is_none() && audience.is_none() && jwks.is_none() {
+            return Ok(None);
+        }
+        let missing = |key: &str| ConfigError::Missing {
+            key: key.to_string(),
+            reason: "authentication needs AUTH_ISSUER, AUTH_AUDIENCE and AUTH_JWKS, or an [auth] section".to_string(),
+        };
+        let file = file.as_ref();
+        Ok(Some(AuthConfig {
+            issuer: issuer.or_else(|| file.map(|auth| auth.issuer.clone())).ok_or_else(|| missing("AUTH_ISSUER"))?,
+            audience: audience.or_else(|| file.map(|auth| auth.audience.clone())).ok_or_else(|| missing("AUTH_AUDIENCE"))?,
+            jwks: jwks.or_else(|| file.map(|auth| auth.jwks.clone())).ok_or_else(|| missing("AUTH_JWKS"))?,
+            jwks_ttl: jwks_ttl.or(file.map(|auth| auth.jwks_ttl)).unwrap_or(crate::auth::DEFAULT_JWKS_TTL.as_secs()),
+        }))
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |key: &str, value: &str, reason: &str| ConfigError::Invalid {
+            key: key.to_string(),
+            value: value.to_string(),
+            reason: reason.to_string(),
+        };
+        let path = &self.docs.path;
+        if !path.starts_with('/') || path.ends_with('/') || path.contains("//") {
+            return Err(invalid("docs path", path, "expected an absolute path below `/` without a trailing slash, such as `/api`"));
+        }
+        if path.contains(['{', '}', '*']) {
+            return Err(invalid("docs path", path, "must not contain route parameters or wildcards"));
+        }
+        // Routes under an API path's fixed prefix would clash with the API's when merged.
+        for api_path in crate::openapi().paths.paths.keys() {
+            let prefix = api_path.split_once("/{").map_or(api_path.as_str(), |(prefix, _)| prefix);
+            if path == prefix || path.starts_with(&format!("{prefix}/")) {
+                let reason = format!("must not be or fall under the API path `{prefix}`");
+                return Err(invalid("docs path", path, &reason));
+            }
+        }
+        if self.auth_disabled && self.auth.is_some() {
+            return Err(invalid("auth_disabled", "true", "authentication cannot be disabled while bearer tokens are configured"));
+        }
+        if !SCALAR_THEMES.contains(&self.docs.theme.as_str()) {
+            let reason = format!("expected one of {}", SCALAR_THEMES.join(", "));
+            return Err(invalid("Scalar theme", &self.docs.theme, &reason));
+        }
+        Ok(())
+    }
+}
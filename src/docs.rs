@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response as HttpResponse};
+use axum::Extension;
+use axum::routing::get;
+use axum::Router;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use serde::Deserialize;
+use sha2::{Digest, Sha512};
+use utoipa::openapi::path::ParameterIn;
+use utoipa::openapi::server::Server;
+use utoipa::openapi::{ContentBuilder, Ref, Response, ResponseBuilder};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_scalar::{Scalar, Servable};
+
+use crate::error::{ApiError, ProblemDetails, PROBLEM_JSON};
+use crate::lambda::BasePath;
+
+// Paths are added by the router in `crate::routes`, not listed here.
+#[derive(OpenApi)]
+#[openapi(
+    components(schemas(ProblemDetails)),
+    modifiers(&SecuritySchemes),
+    security(("bearerAuth" = []), ("apiKey" = [])),
+)]
+/// API
+pub struct ApiDoc;
+
+/// Declares the `bearerAuth` and `apiKey` schemes operations accept, so Scalar can send
+/// either.
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, api: &mut utoipa::openapi::OpenApi) {
+        let bearer = HttpBuilder::new()
+            .scheme(HttpAuthScheme::Bearer)
+            .bearer_format("JWT")
+            .description(Some("RS256 or ES256 token from the configured issuer"))
+            .build();
+        let api_key = ApiKey::Header(ApiKeyValue::with_description(
+            "X-Api-Key",
+            "Key created for a business under `/business/{businessId}/api-keys`",
+        ));
+        let components = api.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("bearerAuth", SecurityScheme::Http(bearer));
+        components.add_security_scheme("apiKey", SecurityScheme::ApiKey(api_key));
+    }
+}
+
+const HTML: &str = r#"
+<!doctype html>
+<html>
+<head>
+    <title>API</title>
+    <meta charset="utf-8"/>
+    <meta
+            name="viewport"
+            content="width=device-width, initial-scale=1"/>
+</head>
+<body>
+
+<script
+        id="api-reference"
+        data-configuration='$configuration'
+        type="application/json">
+    $spec
+</script>
+$bundle
+</body>
+</html>
+"#;
+
+/// Version of the Scalar API reference bundled in `assets/scalar`.
+pub const SCALAR_VERSION: &str = "1.74.0";
+
+/// The pinned Scalar standalone bundle, served by the docs router so the page works offline
+/// and under a `script-src 'self'` content security policy.
+pub static SCALAR_BUNDLE: &[u8] = include_bytes!("../assets/scalar/api-reference-1.74.0.js");
+
+/// SHA-512 of [`SCALAR_BUNDLE`], computed on first use.
+static SCALAR_DIGEST: LazyLock<Vec<u8>> = LazyLock::new(|| Sha512::digest(SCALAR_BUNDLE).to_vec());
+
+/// Themes the bundled Scalar version ships with.
+pub const SCALAR_THEMES: &[&str] = &[
+    "alternate", "default", "moon", "purple", "solarized", "bluePlanet", "deepSpace", "saturn", "kepler", "elysiajs",
+    "fastify", "mars", "laserwave", "none",
+];
+
+/// Where the docs page loads Scalar from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScalarSource {
+    /// The bundle embedded in the binary, served next to the docs page.
+    #[default]
+    Embedded,
+    /// The same pinned version from the jsdelivr CDN.
+    Cdn,
+}
+
+impl FromStr for ScalarSource {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source {
+            "embedded" => Ok(Self::Embedded),
+            "cdn" => Ok(Self::Cdn),
+            other => Err(format!("unknown Scalar source `{other}`, expected `embedded` or `cdn`")),
+        }
+    }
+}
+
+/// How the API reference is served.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DocsConfig {
+    /// Whether the docs page and OpenAPI document are served at all.
+    pub enabled: bool,
+    /// Path of the docs page, such as `/api`; the OpenAPI document and bundle are beneath it.
+    pub path: String,
+    /// One of [`SCALAR_THEMES`].
+    pub theme: String,
+    pub scalar: ScalarSource,
+}
+
+impl Default for DocsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: "/api".to_string(),
+            theme: "laserwave".to_string(),
+            scalar: ScalarSource::default(),
+        }
+    }
+}
+
+/// Serialization of the OpenAPI document.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpecFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl SpecFormat {
+    /// Renders `api`, ending with a newline as a checked-in file would.
+    pub fn render(self, api: &utoipa::openapi::OpenApi) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let mut rendered = match self {
+            Self::Json => api.to_pretty_json()?,
+            Self::Yaml => api.to_yaml()?,
+        };
+        if !rendered.ends_with('\n') {
+            rendered.push('\n');
+        }
+        Ok(rendered)
+    }
+}
+
+impl FromStr for SpecFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            other => Err(format!("unknown format `{other}`, expected `json` or `yaml`")),
+        }
+    }
+}
+
+/// Whether `If-None-Match` already names `etag`, so the client's copy can be reused.
+pub fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// A strong entity tag for content with the given digest.
+fn etag(digest: &[u8]) -> String {
+    format!("\"{}\"", URL_SAFE_NO_PAD.encode(&digest[..16]))
+}
+
+/// Serves the embedded Scalar bundle. Its path is versioned, so it may be cached forever;
+/// `Content-Digest` and the page's `integrity` attribute let clients verify it.
+async fn scalar_bundle(headers: HeaderMap) -> HttpResponse {
+    let etag = etag(&SCALAR_DIGEST);
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, "public, max-age=31536000, immutable".to_string()),
+    ];
+    if etag_matches(&headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    let content_digest = HeaderValue::try_from(format!("sha-512=:{}:", STANDARD.encode(&*SCALAR_DIGEST)))
+        .expect("base64 is a valid header value");
+    (
+        cache_headers,
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("text/javascript; charset=utf-8")),
+            (header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+            (header::HeaderName::from_static("content-digest"), content_digest),
+        ],
+        SCALAR_BUNDLE,
+    )
+        .into_response()
+}
+
+/// The `<script>` tag that loads Scalar on the docs page.
+fn bundle_script(config: &DocsConfig, bundle_path: &str) -> String {
+    match config.scalar {
+        ScalarSource::Embedded => {
+            // Relative to the docs page, so it still resolves behind an API Gateway stage prefix.
+            let page = config.path.rsplit('/').next().unwrap_or_default();
+            let integrity = format!("sha512-{}", STANDARD.encode(&*SCALAR_DIGEST));
+            format!(r#"<script src="{page}{bundle_path}" integrity="{integrity}" crossorigin="anonymous"></script>"#)
+        }
+        ScalarSource::Cdn => format!(
+            r#"<script src="https://cdn.jsdelivr.net/npm/@scalar/api-reference@{SCALAR_VERSION}/dist/browser/standalone.js"></script>"#
+        ),
+    }
+}
+
+/// Most server URLs whose renderings are kept. `Host` is chosen by the client, so renderings
+/// for any further URLs are made per request rather than kept.
+const MAX_RENDERINGS: usize = 16;
+
+/// The OpenAPI document for one server URL, serialized with the entity tag of each format.
+struct Rendering {
+    json: String,
+    json_etag: String,
+    yaml: String,
+    yaml_etag: String,
+}
+
+/// The OpenAPI document, computed once when the router is built, and its renderings for each
+/// server URL it has been requested under.
+struct Spec {
+    api: utoipa::openapi::OpenApi,
+    renderings: RwLock<HashMap<String, Arc<Rendering>>>,
+}
+
+impl Spec {
+    fn new(api: utoipa::openapi::OpenApi) -> Self {
+        Self { api, renderings: RwLock::default() }
+    }
+
+    fn render(&self, server: &str) -> Result<Rendering, ApiError> {
+        let mut api = self.api.clone();
+        api.servers = Some(vec![Server::new(server)]);
+        let json = SpecFormat::Json.render(&api).map_err(ApiError::Internal)?;
+        let yaml = SpecFormat::Yaml.render(&api).map_err(ApiError::Internal)?;
+        Ok(Rendering {
+            json_etag: etag(&Sha512::digest(&json)),
+            json,
+            yaml_etag: etag(&Sha512::digest(&yaml)),
+            yaml,
+        })
+    }
+
+    fn rendering(&self, server: String) -> Result<Arc<Rendering>, ApiError> {
+        if let Some(rendering) = self.renderings.read().unwrap_or_else(|poisoned| poisoned.into_inner()).get(&server) {
+            return Ok(rendering.clone());
+        }
+        let rendering = Arc::new(self.render(&server)?);
+        let mut renderings = self.renderings.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if renderings.len() < MAX_RENDERINGS {
+            renderings.insert(server, rendering.clone());
+        }
+        Ok(rendering)
+    }
+}
+
+/// The base URL clients reached the API at: the forwarded or requested host, and any path
+/// prefix such as an API Gateway stage. Without a host, only the path is known.
+fn server_url(headers: &HeaderMap, base_path: Option<&BasePath>) -> String {
+    let first = |name: &str| {
+        let value = headers.get(name)?.to_str().ok()?;
+        value.split(',').next().map(str::trim).filter(|value| !value.is_empty())
+    };
+    let path = base_path.map(|base_path| base_path.0.as_str()).unwrap_or_default();
+    match first("x-forwarded-host").or_else(|| first(header::HOST.as_str())) {
+        Some(host) => {
+            let scheme = first("x-forwarded-proto").filter(|scheme| *scheme == "https").unwrap_or("http");
+            format!("{scheme}://{host}{path}")
+        }
+        None if path.is_empty() => "/".to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Serves one format of the OpenAPI document, revalidated by its entity tag.
+fn serve_spec(headers: &HeaderMap, body: &str, etag: &str, content_type: &'static str) -> HttpResponse {
+    let cache_headers = [
+        (header::ETAG, etag.to_string()),
+        (header::CACHE_CONTROL, "no-cache".to_string()),
+        (header::VARY, "host, x-forwarded-host, x-forwarded-proto".to_string()),
+    ];
+    if etag_matches(headers, etag) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    (cache_headers, [(header::CONTENT_TYPE, content_type)], body.to_string()).into_response()
+}
+
+async fn openapi_json(
+    State(spec): State<Arc<Spec>>,
+    base_path: Option<Extension<BasePath>>,
+    headers: HeaderMap,
+) -> Result<HttpResponse, ApiError> {
+    let rendering = spec.rendering(server_url(&headers, base_path.as_deref()))?;
+    Ok(serve_spec(&headers, &rendering.json, &rendering.json_etag, "application/json"))
+}
+
+async fn openapi_yaml(
+    State(spec): State<Arc<Spec>>,
+    base_path: Option<Extension<BasePath>>,
+    headers: HeaderMap,
+) -> Result<HttpResponse, ApiError> {
+    let rendering = spec.rendering(server_url(&headers, base_path.as_deref()))?;
+    Ok(serve_spec(&headers, &rendering.yaml, &rendering.yaml_etag, "application/yaml"))
+}
+
+fn problem(description: &str) -> Response {
+    ResponseBuilder::new()
+        .description(description)
+        .content(PROBLEM_JSON, ContentBuilder::new().schema(Some(Ref::from_schema_name("ProblemDetails"))).build())
+        .build()
+}
+
+/// Documents the problem responses the shared extractors and error handling can return:
+/// `400` for a malformed path parameter, `401` for missing or invalid credentials, `403`
+/// when the caller's scopes or businesses do not allow the operation and `500` for any operation.
+pub fn with_common_responses(mut api: utoipa::openapi::OpenApi) -> utoipa::openapi::OpenApi {
+    let bad_path = problem("Malformed path parameter");
+    let unauthorized = problem("Missing or invalid bearer token or API key");
+    let forbidden = problem("Not a member of the business, or missing a required scope");
+    let internal_error = problem("Internal server error");
+    for item in api.paths.paths.values_mut() {
+        let operations = [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+            &mut item.options,
+            &mut item.head,
+            &mut item.patch,
+            &mut item.trace,
+        ];
+        for operation in operations.into_iter().flatten() {
+            let responses = &mut operation.responses.responses;
+            let has_path_parameters = operation
+                .parameters
+                .iter()
+                .flatten()
+                .any(|parameter| parameter.parameter_in == ParameterIn::Path);
+            if has_path_parameters {
+                responses.entry("400".to_string()).or_insert_with(|| bad_path.clone().into());
+            }
+            responses.entry("401".to_string()).or_insert_with(|| unauthorized.clone().into());
+            responses.entry("403".to_string()).or_insert_with(|| forbidden.clone().into());
+            responses.entry("500".to_string()).or_insert_with(|| internal_error.clone().into());
+        }
+    }
+    api
+}
+
+/// Scalar API reference served at `config.path`, with the embedded bundle and the OpenAPI
+/// document as `openapi.json` and `openapi.yaml` beneath it.
+pub fn router(api: utoipa::openapi::OpenApi, config: &DocsConfig) -> Router {
+    let path = &config.path;
+    let bundle_path = format!("/scalar/api-reference-{SCALAR_VERSION}.js");
+    let html = HTML
+        .replace("$configuration", &serde_json::json!({ "theme": config.theme }).to_string())
+        .replace("$bundle", &bundle_script(config, &bundle_path));
+    let spec = Router::new()
+        .route(&format!("{path}/openapi.json"), get(openapi_json))
+        .route(&format!("{path}/openapi.yaml"), get(openapi_yaml))
+        .with_state(Arc::new(Spec::new(api.clone())));
+    let router = Router::new().merge(Scalar::with_url(path.clone(), api).custom_html(html)).merge(spec);
+    match config.scalar {
+        ScalarSource::Embedded => router.route(&format!("{path}{bundle_path}"), get(scalar_bundle)),
+        ScalarSource::Cdn => router,
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{header, HeaderName, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use lambda_http::request::RequestContext;
+use lambda_http::RequestExt;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::repository::RepositoryError;
+
+/// Media type of an RFC 7807 problem document.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Header carrying the id that ties a request to its logs; echoed on every response.
+pub const CORRELATION_ID: HeaderName = HeaderName::from_static("x-correlation-id");
+
+/// Problem Details
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Error response as described by RFC 7807.
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    #[schema(
+        example = "/problems/user-not-found",
+    )]
+    /// URI reference identifying the kind of problem.
+    pub problem_type: String,
+    #[schema(
+        example = "User not found",
+    )]
+    /// Short summary of the kind of problem.
+    pub title: String,
+    #[schema(
+        example = 404,
+    )]
+    /// HTTP status code.
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Explanation specific to this occurrence of the problem.
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(
+        example = "/business/7c9e6679-7425-40de-944b-e07fc1f90ae7/users/550e8400-e29b-41d4-a716-446655440000",
+    )]
+    /// Path of the request that caused the problem.
+    pub instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(
+        example = "0d9f3b8e-6a43-4a8e-9f0b-6f4a3c2d1e5b",
+    )]
+    /// Id to quote when reporting the problem; also sent as `X-Correlation-Id`.
+    pub correlation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Problems with individual fields or parameters, keyed by name.
+    pub errors: Option<BTreeMap<String, String>>,
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = serde_json::to_vec(&self).expect("problem details serialize to JSON");
+        let mut response = (status, [(header::CONTENT_TYPE, PROBLEM_JSON)], body).into_response();
+        // Kept so that `problem_details` can add the request's instance and correlation id.
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+/// Every error a handler can return.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("No business with this id exists.")]
+    BusinessNotFound,
+    #[error("A business with this id already exists.")]
+    BusinessExists,
+    #[error("Another business already has this slug.")]
+    SlugTaken,
+    #[error("The business is archived; restore it to change it.")]
+    BusinessArchived,
+    #[error("No user with this id exists in the business.")]
+    UserNotFound,
+    #[error("The user has been deleted; restore it to use it again.")]
+    UserDeleted,
+    #[error("A user with this id already exists in the business.")]
+    UserExists,
+    #[error("Another user in the business already has this email.")]
+    EmailTaken,
+    #[error("The resource was modified by another request; retry with the latest version.")]
+    ConcurrentUpdate,
+    #[error("One or more fields are invalid.")]
+    Validation(BTreeMap<String, String>),
+    #[error("One or more path parameters are malformed.")]
+    InvalidPath(BTreeMap<String, String>),
+    #[error("{0}")]
+    Unprocessable(String),
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+    #[error("The request has no bearer token or API key.")]
+    MissingCredentials,
+    #[error("The bearer token is not valid: {0}.")]
+    InvalidToken(String),
+    #[error("The API key is not valid: {0}.")]
+    InvalidApiKey(String),
+    #[error("No API key with this prefix exists in the business.")]
+    ApiKeyNotFound,
+    #[error("The caller is not a member of business {0}.")]
+    NotAMember(uuid::Uuid),
+    #[error("The operation needs the `{0}` scope.")]
+    InsufficientScope(String),
+    #[error("The server failed to handle the request.")]
+    Internal(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl ApiError {
+    /// Problem type slug, title and status.
+    fn kind(&self) -> (&'static str, &'static str, StatusCode) {
+        match self {
+            Self::BusinessNotFound => ("business-not-found", "Business not found", StatusCode::NOT_FOUND),
+            Self::BusinessExists => ("business-exists", "Business already exists", StatusCode::CONFLICT),
+            Self::SlugTaken => ("slug-taken", "Slug already in use", StatusCode::CONFLICT),
+            Self::BusinessArchived => ("business-archived", "Business archived", StatusCode::CONFLICT),
+            Self::UserNotFound => ("user-not-found", "User not found", StatusCode::NOT_FOUND),
+            Self::UserDeleted => ("user-deleted", "User deleted", StatusCode::GONE),
+            Self::UserExists => ("user-exists", "User already exists", StatusCode::CONFLICT),
+            Self::EmailTaken => ("email-taken", "Email already in use", StatusCode::CONFLICT),
+            Self::ConcurrentUpdate => ("concurrent-update", "Concurrent update", StatusCode::CONFLICT),
+            Self::Validation(_) => ("validation-error", "Invalid fields", StatusCode::UNPROCESSABLE_ENTITY),
+            Self::InvalidPath(_) => ("invalid-path-parameter", "Invalid path parameter", StatusCode::BAD_REQUEST),
+            Self::Unprocessable(_) => ("unprocessable", "Unprocessable request", StatusCode::UNPROCESSABLE_ENTITY),
+            Self::BadRequest(_) => ("bad-request", "Bad request", StatusCode::BAD_REQUEST),
+            Self::UnsupportedMediaType(_) => {
+                ("unsupported-media-type", "Unsupported media type", StatusCode::UNSUPPORTED_MEDIA_TYPE)
+            }
+            Self::MissingCredentials => ("missing-credentials", "Missing credentials", StatusCode::UNAUTHORIZED),
+            Self::InvalidToken(_) => ("invalid-token", "Invalid token", StatusCode::UNAUTHORIZED),
+            Self::InvalidApiKey(_) => ("invalid-api-key", "Invalid API key", StatusCode::UNAUTHORIZED),
+            Self::ApiKeyNotFound => ("api-key-not-found", "API key not found", StatusCode::NOT_FOUND),
+            Self::NotAMember(_) => ("not-a-member", "Not a member of the business", StatusCode::FORBIDDEN),
+            Self::InsufficientScope(_) => ("insufficient-scope", "Insufficient scope", StatusCode::FORBIDDEN),
+            Self::Internal(_) => ("internal-error", "Internal server error", StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+}
+
+/// Errors whose meaning does not depend on the resource. `NotFound` and `Conflict` do, so
+/// call sites map them to the resource's problem; any that get here were not expected.
+impl From<RepositoryError> for ApiError {
+    fn from(error: RepositoryError) -> Self {
+        match error {
+            error @ (RepositoryError::NotFound | RepositoryError::Conflict) => Self::Internal(error.into()),
+            RepositoryError::EmailTaken => Self::EmailTaken,
+            RepositoryError::SlugTaken => Self::SlugTaken,
+            RepositoryError::ConcurrentUpdate => Self::ConcurrentUpdate,
+            RepositoryError::Invalid(detail) => Self::Unprocessable(detail),
+            RepositoryError::Backend(source) => Self::Internal(source),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let Self::Internal(source) = &self {
+            tracing::error!(error = %source, "request failed");
+        }
+        let (slug, title, status) = self.kind();
+        let detail = self.to_string();
+        // RFC 6750 challenge telling the client how to authenticate.
+        let challenge = match &self {
+            Self::MissingCredentials => Some(r#"Bearer realm="api""#.to_string()),
+            Self::InvalidToken(_) => Some(r#"Bearer realm="api", error="invalid_token""#.to_string()),
+            Self::InsufficientScope(scope) => Some(format!(r#"Bearer realm="api", error="insufficient_scope", scope="{scope}""#)),
+            _ => None,
+        };
+        let errors = match self {
+            Self::Validation(errors) | Self::InvalidPath(errors) => Some(errors),
+            _ => None,
+        };
+        let problem = ProblemDetails {
+            problem_type: format!("/problems/{slug}"),
+            title: title.to_string(),
+            status: status.as_u16(),
+            detail: Some(detail),
+            instance: None,
+            correlation_id: None,
+            errors,
+        };
+        let mut response = problem.into_response();
+        if let Some(challenge) = challenge.and_then(|challenge| HeaderValue::try_from(challenge).ok()) {
+            response.headers_mut().insert(header::WWW_AUTHENTICATE, challenge);
+        }
+        response
+    }
+}
+
+/// Uses the caller's `X-Correlation-Id` if it is reasonable, then the API Gateway request id,
+/// and otherwise makes one up.
+fn correlation_id(request: &Request) -> HeaderValue {
+    if let Some(id) = request.headers().get(CORRELATION_ID)
+        && !id.is_empty()
+        && id.len() <= 128
+        && id.to_str().is_ok()
+    {
+        return id.clone();
+    }
+    let request_id = match request.request_context_ref() {
+        Some(RequestContext::ApiGatewayV1(context)) => context.request_id.clone(),
+        Some(RequestContext::ApiGatewayV2(context)) => context.request_id.clone(),
+        _ => None,
+    };
+    request_id
+        .and_then(|id| HeaderValue::from_str(&id).ok())
+        .unwrap_or_else(|| HeaderValue::from_str(&uuid::Uuid::new_v4().to_string()).expect("uuid is a valid header"))
+}
+
+/// Middleware that tags every response with a correlation id and completes problem
+/// responses with the request path and that id.
+pub async fn problem_details(request: Request, next: Next) -> Response {
+    let correlation_id = correlation_id(&request);
+    let instance = request.uri().path().to_string();
+
+    let mut response = next.run(request).await;
+    if let Some(mut problem) = response.extensions_mut().remove::<ProblemDetails>() {
+        problem.instance = Some(instance);
+        problem.correlation_id = correlation_id.to_str().ok().map(str::to_string);
+        let (mut parts, _) = response.into_parts();
+        parts.headers.remove(header::CONTENT_LENGTH);
+        let body = serde_json::to_vec(&problem).expect("problem details serialize to JSON");
+        response = Response::from_parts(parts, Body::from(body));
+    }
+    response.headers_mut().insert(CORRELATION_ID, correlation_id);
+    response
+}
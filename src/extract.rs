@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, FromRequestParts, RawPathParams, Request};
+use axum::http::request::Parts;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::validation;
+
+/// Reads the named path parameters as UUIDs, reporting every malformed one.
+fn uuid_params<const N: usize>(params: &RawPathParams, names: [&str; N]) -> Result<[Uuid; N], ApiError> {
+    let mut values = [Uuid::nil(); N];
+    let mut errors = BTreeMap::new();
+    for (value, name) in values.iter_mut().zip(names) {
+        let raw = params.iter().find(|(key, _)| *key == name).map(|(_, raw)| raw);
+        match raw.map(str::parse::<Uuid>) {
+            Some(Ok(uuid)) => *value = uuid,
+            Some(Err(_)) => {
+                let raw = raw.unwrap_or_default();
+                errors.insert(name.to_string(), format!("must be a UUID such as 550e8400-e29b-41d4-a716-446655440000, got `{raw}`"));
+            }
+            None => {
+                errors.insert(name.to_string(), "is missing".to_string());
+            }
+        }
+    }
+    if errors.is_empty() { Ok(values) } else { Err(ApiError::InvalidPath(errors)) }
+}
+
+async fn raw_params<S: Send + Sync>(parts: &mut Parts, state: &S) -> Result<RawPathParams, ApiError> {
+    RawPathParams::from_request_parts(parts, state)
+        .await
+        .map_err(|rejection| ApiError::Internal(rejection.body_text().into()))
+}
+
+/// The `businessId` path parameter.
+pub struct BusinessPath(pub Uuid);
+
+impl<S: Send + Sync> FromRequestParts<S> for BusinessPath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let [business_id] = uuid_params(&raw_params(parts, state).await?, ["businessId"])?;
+        Ok(Self(business_id))
+    }
+}
+
+/// The `businessId` and `userId` path parameters.
+pub struct UserPath(pub Uuid, pub Uuid);
+
+impl<S: Send + Sync> FromRequestParts<S> for UserPath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let [business_id, user_id] = uuid_params(&raw_params(parts, state).await?, ["businessId", "userId"])?;
+        Ok(Self(business_id, user_id))
+    }
+}
+
+/// The `businessId` and `prefix` path parameters of an API key.
+pub struct ApiKeyPath(pub Uuid, pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for ApiKeyPath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let params = raw_params(parts, state).await?;
+        let [business_id] = uuid_params(&params, ["businessId"])?;
+        let prefix = params.iter().find(|(key, _)| *key == "prefix").map(|(_, prefix)| prefix.to_string());
+        Ok(Self(business_id, prefix.unwrap_or_default()))
+    }
+}
+
+/// Query string, rejected as a problem document.
+pub struct Query<T>(pub T);
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequestParts<S> for Query<T> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(query) = axum::extract::Query::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+        Ok(Self(query))
+    }
+}
+
+/// A JSON object request body, checked for syntax but not for its fields.
+pub struct JsonObject(pub Map<String, Value>);
+
+impl<S: Send + Sync> FromRequest<S> for JsonObject {
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body = axum::Json::<Value>::from_request(request, state).await.map_err(|rejection| match rejection {
+            JsonRejection::MissingJsonContentType(_) => {
+                ApiError::UnsupportedMediaType("Expected request with `Content-Type: application/json`".to_string())
+            }
+            rejection => ApiError::BadRequest(rejection.body_text()),
+        })?;
+        match body.0 {
+            Value::Object(document) => Ok(Self(document)),
+            _ => Err(ApiError::Unprocessable("Request body must be a JSON object".to_string())),
+        }
+    }
+}
+
+/// A JSON request body, checked against the schema `T` documents before it is deserialized
+/// so that every bad field is reported rather than only the first.
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned + ToSchema, S: Send + Sync> FromRequest<S> for Json<T> {
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let JsonObject(document) = JsonObject::from_request(request, state).await?;
+        let errors = validation::check::<T>(&document);
+        if !errors.is_empty() {
+            return Err(ApiError::Validation(errors));
+        }
+        serde_json::from_value(Value::Object(document))
+            .map(Self)
+            .map_err(|error| ApiError::Unprocessable(error.to_string()))
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeSet;
+
+use axum::http::Extensions;
+use axum::Router;
+use lambda_http::aws_lambda_events::apigw::ApiGatewayRequestAuthorizer;
+use lambda_http::request::RequestContext;
+use lambda_http::{Request, RequestExt};
+use serde_json::Value;
+use tower::ServiceBuilder;
+use tower::util::MapRequest;
+
+use crate::auth::Principal;
+
+/// Wraps the router so it can handle API Gateway REST (v1), HTTP API (v2) and ALB events.
+pub fn service(app: Router) -> MapRequest<Router, fn(Request) -> Request> {
+    ServiceBuilder::new()
+        .map_request(strip_stage as fn(Request) -> Request)
+        .service(app)
+}
+
+/// Whether the process was started by the Lambda runtime rather than by hand.
+pub fn is_lambda_runtime() -> bool {
+    std::env::var_os("AWS_LAMBDA_RUNTIME_API").is_some()
+}
+
+/// Runs the router on the Lambda runtime.
+pub async fn run(app: Router) -> Result<(), lambda_http::Error> {
+    lambda_http::run(service(app)).await
+}
+
+/// The path prefix removed from a request before routing, such as `/prod` for an API Gateway
+/// stage, so that handlers can still build URLs clients can follow.
+#[derive(Clone, Debug)]
+pub struct BasePath(pub String);
+
+/// API Gateway prefixes the path with the stage name, which the router knows nothing about.
+fn strip_stage(mut request: Request) -> Request {
+    let stage = match request.request_context_ref() {
+        Some(RequestContext::ApiGatewayV1(context)) => context.stage.clone(),
+        Some(RequestContext::ApiGatewayV2(context)) => context.stage.clone(),
+        _ => None,
+    };
+    let Some(stage) = stage.filter(|stage| stage != "$default") else {
+        return request;
+    };
+
+    let uri = request.uri();
+    let prefix = format!("/{stage}");
+    let Some(path) = uri.path().strip_prefix(&prefix) else {
+        return request;
+    };
+    if !path.is_empty() && !path.starts_with('/') {
+        return request;
+    }
+    let path = if path.is_empty() { "/" } else { path };
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
+
+    let mut parts = uri.clone().into_parts();
+    if let Ok(path_and_query) = path_and_query.parse() {
+        parts.path_and_query = Some(path_and_query);
+        if let Ok(uri) = axum::http::Uri::from_parts(parts) {
+            *request.uri_mut() = uri;
+            request.extensions_mut().insert(BasePath(prefix));
+        }
+    }
+    request
+}
+
+/// The identity an API Gateway authorizer has already verified, read from the event's
+/// `requestContext.authorizer`: Cognito user pool claims or a Lambda authorizer's context on a
+/// REST API, and JWT claims or a Lambda authorizer's context on an HTTP API.
+///
+/// The subject is `sub`, or else `principalId`; scopes come from `scope` and, for JWT
+/// authorizers, the scopes API Gateway lists; businesses from `businesses` or
+/// `custom:businesses`. Authorizers pass claims and context on as strings, so lists may be a
+/// JSON array, `[a b]` or separated by commas or spaces.
+pub fn authorizer_principal(extensions: &Extensions) -> Option<Principal> {
+    match extensions.get::<RequestContext>()? {
+        RequestContext::ApiGatewayV1(context) => {
+            let fields = &context.authorizer.fields;
+            match fields.get("claims").and_then(Value::as_object) {
+                Some(claims) => principal(|name| claims.get(name).cloned(), &[]),
+                None => principal(|name| fields.get(name).cloned(), &[]),
+            }
+        }
+        RequestContext::ApiGatewayV2(context) => match context.authorizer.as_ref()? {
+            ApiGatewayRequestAuthorizer { jwt: Some(jwt), .. } => {
+                let scopes = jwt.scopes.as_deref().unwrap_or_default();
+                principal(|name| jwt.claims.get(name).cloned().map(Value::String), scopes)
+            }
+            authorizer => principal(|name| authorizer.fields.get(name).cloned(), &[]),
+        },
+        _ => None,
+    }
+}
+
+/// The principal described by the claims `claim` looks up, if it names a subject.
+fn principal(claim: impl Fn(&str) -> Option<Value>, scopes: &[String]) -> Option<Principal> {
+    let subject = ["sub", "principalId"].into_iter().find_map(|name| claim(name)?.as_str().map(str::to_string))?;
+    let businesses = ["businesses", "custom:businesses"].into_iter().find_map(&claim);
+    Some(Principal {
+        subject,
+        businesses: list(businesses.as_ref()).iter().filter_map(|id| id.parse().ok()).collect(),
+        scopes: list(claim("scope").as_ref()).into_iter().chain(scopes.iter().cloned()).collect(),
+    })
+}
+
+/// The items of a claim that is a JSON array or a string listing them.
+fn list(value: Option<&Value>) -> BTreeSet<String> {
+    let separator = |c: char| c.is_whitespace() || matches!(c, ',' | '[' | ']' | '"');
+    match value {
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+        Some(Value::String(items)) => items.split(separator).filter(|item| !item.is_empty()).map(str::to_string).collect(),
+        _ => BTreeSet::new(),
+    }
+}
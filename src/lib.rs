@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use utoipa::OpenApi;
+use utoipa_axum::router::OpenApiRouter;
+use utoipa_axum::routes;
+
+use crate::auth::Authenticator;
+use crate::docs::{ApiDoc, DocsConfig};
+use crate::policy::Policy;
+use crate::repository::memory::InMemoryStore;
+use crate::repository::{ApiKeyRepository, BusinessRepository, UserRepository};
+use crate::search::{Indexed, UserSearch};
+
+pub mod api_keys;
+pub mod auth;
+pub mod businesses;
+pub mod compat;
+pub mod config;
+pub mod docs;
+pub mod error;
+pub mod extract;
+pub mod lambda;
+pub mod pagination;
+pub mod patch;
+pub mod policy;
+pub mod repository;
+pub mod search;
+pub mod users;
+pub mod validation;
+
+/// Shared handler state.
+#[derive(Clone)]
+pub struct AppState {
+    pub businesses: Arc<dyn BusinessRepository>,
+    pub users: Arc<dyn UserRepository>,
+    pub search: Arc<UserSearch>,
+    pub api_keys: Arc<dyn ApiKeyRepository>,
+    /// Verifies bearer tokens; without one, only API keys and API Gateway authorizer
+    /// identities are accepted.
+    pub auth: Option<Arc<Authenticator>>,
+    /// Whether requests with no credentials at all are let through as anonymous.
+    pub anonymous: bool,
+}
+
+impl AppState {
+    /// Keeps businesses and users in the same store.
+    pub fn new(store: impl BusinessRepository + UserRepository + 'static) -> Self {
+        let store = Arc::new(store);
+        Self::shared(store.clone()).with_businesses(store)
+    }
+
+    /// Wraps the store so that writes keep the search index up to date. Businesses and API
+    /// keys are kept in memory unless [`with_businesses`](Self::with_businesses) and
+    /// [`with_api_keys`](Self::with_api_keys) say otherwise.
+    pub fn shared(users: Arc<dyn UserRepository>) -> Self {
+        let search = Arc::new(UserSearch::default());
+        Self {
+            businesses: Arc::new(InMemoryStore::default()),
+            users: Arc::new(Indexed { users, search: search.clone() }),
+            search,
+            api_keys: Arc::new(InMemoryStore::default()),
+            auth: None,
+            anonymous: false,
+        }
+    }
+
+    pub fn with_businesses(self, businesses: Arc<dyn BusinessRepository>) -> Self {
+        Self { businesses, ..self }
+    }
+
+    pub fn with_api_keys(self, api_keys: Arc<dyn ApiKeyRepository>) -> Self {
+        Self { api_keys, ..self }
+    }
+
+    /// Requires a valid bearer token, API key or API Gateway authorizer identity on every API
+    /// route, carrying the scopes and business membership the route declares.
+    pub fn with_auth(self, auth: Authenticator) -> Self {
+        Self { auth: Some(Arc::new(auth)), ..self }
+    }
+
+    /// Lets requests without credentials through as anonymous, so that no membership or
+    /// scope is checked for them. Only for local development and tests.
+    pub fn with_anonymous_access(self) -> Self {
+        Self { anonymous: true, ..self }
+    }
+}
+
+/// Registers every documented route; the OpenAPI paths are derived from this same list,
+/// so a handler cannot be routed without being documented or documented without being routed.
+fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .routes(routes!(businesses::list_businesses, businesses::create_business))
+        .routes(routes!(businesses::get_business, businesses::update_business))
+        .routes(routes!(businesses::archive_business))
+        .routes(routes!(businesses::restore_business))
+        .routes(routes!(users::get_user_by_id, users::replace_user, users::patch_user, users::delete_user))
+        .routes(routes!(users::restore_user))
+        .routes(routes!(users::list_users, users::create_user))
+        .routes(routes!(users::search_users))
+        .routes(routes!(api_keys::list_api_keys, api_keys::create_api_key))
+        .routes(routes!(api_keys::revoke_api_key))
+}
+
+/// The OpenAPI document for every registered route.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    docs::with_common_responses(routes().into_openapi())
+}
+
+/// Builds the application router shared by the local server and the Lambda runtime.
+pub fn app(state: AppState) -> axum::Router {
+    app_with_docs(state, &DocsConfig::default())
+}
+
+/// [`app`], with the API reference served as `docs` says.
+pub fn app_with_docs(state: AppState, docs: &DocsConfig) -> axum::Router {
+    let (router, api) = routes().split_for_parts();
+    // Installed even without an authenticator, as API keys and API Gateway authorizers still
+    // identify callers and requests without credentials must still be refused.
+    // Route layers, so that unknown paths are still a 404 rather than a 401. The last added
+    // runs first.
+    let mut router = router
+        .route_layer(axum::middleware::from_fn_with_state(Arc::new(Policy::new(&api)), policy::authorize))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), auth::authenticate))
+        .with_state(state);
+    if docs.enabled {
+        router = router.merge(docs::router(docs::with_common_responses(api), docs));
+    }
+    router.layer(axum::middleware::from_fn(error::problem_details))
+}
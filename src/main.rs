@@ -1,119 +1,149 @@
 use std::net::SocketAddr;
-use axum::routing::get;
-use utoipa::{OpenApi, ToSchema};
-use utoipa_scalar::{Scalar, Servable};
-use serde::{Serialize, Deserialize};
-use axum::{extract::Path, response::IntoResponse, http::StatusCode};
-use uuid::Uuid;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
 
-#[derive(OpenApi)]
-#[openapi(paths(get_user_by_id))]
-/// API
-struct ApiDoc;
+use clap::{Parser, Subcommand};
 
-/// User Account
-#[derive(Serialize, Deserialize, ToSchema)]
-#[serde(rename_all = "camelCase")]
-/// Represents a user account within the business.
-pub struct User {
-    #[schema(
-        example = "550e8400-e29b-41d4-a716-446655440000",
-    )]
-    /// Unique identifier for the user.
-    pub uuid: Uuid,
-    #[schema(
-        example = "Jane",
-    )]
-    /// First name of the user.
-    pub first_name: String,
-    #[schema(
-        example = "Doe",
-    )]
-    /// Last name of the user.
-    pub last_name: String,
-    #[schema(
-        example = "jane.doe@example.com",
-    )]
-    /// Email address of the user.
-    pub email: String,
-    #[schema(
-        example = true,
-    )]
-    /// Whether the user's account is enabled.
-    pub enabled: bool,
-    #[schema(
-        example = true,
-    )]
-    /// Whether the user's account is activated.
-    pub activated: bool,
+use rust_lambda_api_poc::repository::dynamodb::DynamoDbStore;
+use rust_lambda_api_poc::repository::memory::InMemoryStore;
+use rust_lambda_api_poc::repository::sqlite::SqliteStore;
+use rust_lambda_api_poc::repository::{ApiKeyRepository, BusinessRepository, UserRepository};
+use rust_lambda_api_poc::auth::Authenticator;
+use rust_lambda_api_poc::config::{Config, Storage};
+use rust_lambda_api_poc::docs::SpecFormat;
+use rust_lambda_api_poc::{app_with_docs, compat, lambda, openapi, AppState};
+
+#[derive(Parser)]
+#[command(version, about = "Users API, served locally or on AWS Lambda")]
+struct Cli {
+    /// TOML config file; defaults to the file named by `CONFIG_FILE`, if set.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Serve the API; the default when no subcommand is given.
+    Serve,
+    /// Write the OpenAPI document and exit.
+    Openapi {
+        /// `json` or `yaml`.
+        #[arg(long, default_value = "json")]
+        format: SpecFormat,
+        /// File to write; standard output if omitted.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Compare the OpenAPI document with a baseline and fail on breaking changes.
+    Diff {
+        /// Baseline OpenAPI document, in JSON or YAML.
+        #[arg(long)]
+        baseline: PathBuf,
+        /// Location of a breaking change to accept, as printed; may be repeated.
+        #[arg(long, value_name = "LOCATION")]
+        allow: Vec<String>,
+    },
 }
 
-/// Get user account by user id
-#[utoipa::path(
-    get,
-    path = "/business/{businessId}/users/{userId}",
-    responses(
-        (status = 200, description = "User", body = User)
-    ),
-    params(
-        ("businessId" = Uuid, Path, description = "Business id of the user"),
-        ("userId" = String, Path, description = "User id to get user"),
-    )
-)]
-async fn get_user_by_id(Path(user_id): Path<Uuid>) -> impl IntoResponse {
-    if user_id == Uuid::nil() {
-        return (StatusCode::NOT_FOUND, "User not found").into_response();
+/// Prints every change from `baseline` and whether the breaking ones were allowed.
+/// Returns whether any breaking change was not.
+fn report_diff(baseline: &std::path::Path, allow: &[String]) -> Result<bool, lambda_http::Error> {
+    let baseline = compat::parse(&std::fs::read_to_string(baseline)?)?;
+    let changes = compat::diff(&baseline, &serde_json::to_value(openapi())?);
+    let mut failed = false;
+    for change in &changes {
+        let label = match (change.breaking, allow.contains(&change.location)) {
+            (false, _) => "non-breaking",
+            (true, true) => "allowed",
+            (true, false) => {
+                failed = true;
+                "BREAKING"
+            }
+        };
+        println!("{label:>12}  {change}");
     }
-    let user = User {
-        uuid: user_id,
-        first_name: "Jane".to_string(),
-        last_name: "Doe".to_string(),
-        email: "jane.doe@example.com".to_string(),
-        enabled: true,
-        activated: true,
-    };
-    match serde_json::to_string(&user) {
-        Ok(body) => (StatusCode::OK, body).into_response(),
-        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Unknown error").into_response(),
+    if changes.is_empty() {
+        println!("no changes");
     }
+    Ok(failed)
+}
+
+/// The configured store, which keeps businesses, users and API keys alike.
+async fn store(storage: &Storage) -> Result<AppState, lambda_http::Error> {
+    fn state<T: BusinessRepository + UserRepository + ApiKeyRepository + 'static>(store: T) -> AppState {
+        let store = Arc::new(store);
+        AppState::shared(store.clone()).with_businesses(store.clone()).with_api_keys(store)
+    }
+    Ok(match storage {
+        Storage::DynamoDb { table, endpoint } => state(DynamoDbStore::from_env(table.clone(), endpoint.clone()).await),
+        Storage::Sqlite { url } => state(SqliteStore::connect(url).await?),
+        Storage::Memory { fixture: Some(path) } => {
+            let fixture = std::fs::read_to_string(path)
+                .map_err(|error| format!("could not read users fixture {}: {error}", path.display()))?;
+            state(InMemoryStore::from_json(&fixture)?)
+        }
+        Storage::Memory { fixture: None } => state(InMemoryStore::default()),
+    })
 }
 
 #[tokio::main]
-async fn main() {
-    let socket_address: SocketAddr = "127.0.0.1:8080".parse().unwrap();
-    let listener = tokio::net::TcpListener::bind(socket_address).await.unwrap();
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    let html = r#"
-<!doctype html>
-<html>
-<head>
-    <title>API</title>
-    <meta charset="utf-8"/>
-    <meta
-            name="viewport"
-            content="width=device-width, initial-scale=1"/>
-</head>
-<body>
+async fn run(cli: Cli) -> Result<ExitCode, lambda_http::Error> {
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(Config::load(cli.config.as_deref())?).await?,
+        Command::Openapi { format, out } => {
+            let spec = format.render(&openapi())?;
+            match out {
+                Some(path) => std::fs::write(path, spec)?,
+                None => print!("{spec}"),
+            }
+        }
+        Command::Diff { baseline, allow } => {
+            if report_diff(&baseline, &allow)? {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
 
-<script
-        id="api-reference"
-        data-configuration='{"theme":"laserwave"}'
-        type="application/json">
-    $spec
-</script>
-<script src="https://cdn.jsdelivr.net/npm/@scalar/api-reference"></script>
-</body>
-</html>
-"#;
+async fn serve(config: Config) -> Result<(), lambda_http::Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(config.log_level)
+        .with_ansi(!lambda::is_lambda_runtime())
+        .init();
 
-    let app = axum::Router::new()
-        .route("/users/{user_id}", get(get_user_by_id))
-        .merge(Scalar::with_url(
-            "/api",
-            ApiDoc::openapi()
-        ).custom_html(html));
+    let mut state = store(&config.storage).await?;
+    if let Some(auth) = &config.auth {
+        state = state.with_auth(Authenticator::new(auth));
+    }
+    if config.auth_disabled {
+        tracing::warn!("authentication is disabled; requests without an API key or authorizer are anonymous");
+        state = state.with_anonymous_access();
+    }
+    let app = app_with_docs(state, &config.docs);
+
+    if lambda::is_lambda_runtime() {
+        return lambda::run(app).await;
+    }
 
-    axum::serve(listener, app.into_make_service())
+    let address = SocketAddr::new(config.host, config.port);
+    let listener = tokio::net::TcpListener::bind(address)
         .await
-        .unwrap()
+        .map_err(|error| format!("could not listen on {address}: {error}"))?;
+    tracing::info!("listening on http://{address}");
+    axum::serve(listener, app.into_make_service()).await?;
+    Ok(())
 }
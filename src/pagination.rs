@@ -0,0 +1,37 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Default number of items in a page.
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// Largest number of items a client may ask for in one page.
+pub const MAX_LIMIT: usize = 100;
+
+/// Page
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// One page of results.
+pub struct Page<T> {
+    /// Items in this page.
+    pub items: Vec<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(
+        example = "eyJzb3J0IjoibmFtZSJ9",
+    )]
+    /// Opaque cursor for the next page; absent on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Encodes cursor state as an opaque URL-safe string.
+pub fn encode_cursor<T: Serialize>(cursor: &T) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).expect("cursor serializes to JSON"))
+}
+
+/// Decodes a cursor produced by [`encode_cursor`], or `None` if it was tampered with.
+pub fn decode_cursor<T: DeserializeOwned>(cursor: &str) -> Option<T> {
+    let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    serde_json::from_slice(&json).ok()
+}
@@ -0,0 +1,62 @@
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Request};
+use axum::http::header;
+use serde_json::{Map, Value};
+
+use crate::error::ApiError;
+
+/// Media type of a JSON Merge Patch document (RFC 7396).
+pub const MERGE_PATCH_JSON: &str = "application/merge-patch+json";
+
+/// A JSON Merge Patch request body. Only objects are accepted, as every patchable
+/// resource is an object.
+pub struct MergePatch(pub Map<String, Value>);
+
+impl<S: Send + Sync> FromRequest<S> for MergePatch {
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(str::trim);
+        if content_type != Some(MERGE_PATCH_JSON) {
+            return Err(ApiError::UnsupportedMediaType(format!(
+                "Expected request with `Content-Type: {MERGE_PATCH_JSON}`"
+            )));
+        }
+
+        let body = Bytes::from_request(request, state)
+            .await
+            .map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+        match serde_json::from_slice(&body) {
+            Ok(Value::Object(patch)) => Ok(Self(patch)),
+            Ok(_) => Err(ApiError::Unprocessable("Merge patch must be a JSON object".to_string())),
+            Err(error) => Err(ApiError::BadRequest(format!("Invalid JSON: {error}"))),
+        }
+    }
+}
+
+/// Applies `patch` to `target` as described by RFC 7396: `null` removes a member,
+/// objects merge recursively and any other value replaces the member.
+pub fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!("target was just made an object");
+    };
+    for (name, value) in patch {
+        if value.is_null() {
+            target.remove(name);
+        } else {
+            merge(target.entry(name.as_str()).or_insert(Value::Null), value);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use axum::extract::{MatchedPath, RawPathParams, Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::RequestExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::openapi::OpenApi;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::Principal;
+use crate::error::ApiError;
+
+/// Path parameter naming the business an operation acts on.
+const BUSINESS_PARAMETER: &str = "businessId";
+
+/// Scope an operation can require. Tokens may carry scopes of other APIs, which are ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+pub enum Scope {
+    /// Read users.
+    #[serde(rename = "users:read")]
+    UsersRead,
+    /// Create, change and delete users.
+    #[serde(rename = "users:write")]
+    UsersWrite,
+    /// List API keys.
+    #[serde(rename = "api-keys:read")]
+    ApiKeysRead,
+    /// Create and revoke API keys.
+    #[serde(rename = "api-keys:write")]
+    ApiKeysWrite,
+    /// Read businesses.
+    #[serde(rename = "businesses:read")]
+    BusinessesRead,
+    /// Create, change and archive businesses.
+    #[serde(rename = "businesses:write")]
+    BusinessesWrite,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UsersRead => "users:read",
+            Self::UsersWrite => "users:write",
+            Self::ApiKeysRead => "api-keys:read",
+            Self::ApiKeysWrite => "api-keys:write",
+            Self::BusinessesRead => "businesses:read",
+            Self::BusinessesWrite => "businesses:write",
+        }
+    }
+}
+
+/// What each operation requires of the caller, read from the security requirements of the
+/// OpenAPI document so that the rules enforced are the rules documented.
+pub struct Policy {
+    /// Alternative sets of scopes per method and route; any one set is enough, and no sets
+    /// at all means no scope is needed.
+    rules: HashMap<(Method, String), Vec<BTreeSet<String>>>,
+}
+
+impl Policy {
+    pub fn new(api: &OpenApi) -> Self {
+        let mut rules = HashMap::new();
+        for (path, item) in &api.paths.paths {
+            let operations = [
+                (Method::GET, &item.get),
+                (Method::PUT, &item.put),
+                (Method::POST, &item.post),
+                (Method::DELETE, &item.delete),
+                (Method::OPTIONS, &item.options),
+                (Method::HEAD, &item.head),
+                (Method::PATCH, &item.patch),
+                (Method::TRACE, &item.trace),
+            ];
+            for (method, operation) in operations {
+                let Some(operation) = operation else {
+                    continue;
+                };
+                // An operation without its own requirements falls back to the document's.
+                let requirements = operation.security.as_ref().or(api.security.as_ref());
+                let alternatives = requirements
+                    .into_iter()
+                    .flatten()
+                    .map(|requirement| {
+                        // Scopes required of whichever scheme the caller used.
+                        let schemes = serde_json::to_value(requirement).unwrap_or_default();
+                        let scopes = schemes.as_object().into_iter().flat_map(|schemes| schemes.values());
+                        scopes.filter_map(Value::as_array).flatten().filter_map(Value::as_str).map(str::to_string).collect()
+                    })
+                    .collect();
+                rules.insert((method, path.clone()), alternatives);
+            }
+        }
+        Self { rules }
+    }
+
+    /// Allows `principal` to call the operation at `method` and `route` on `business`, or says why not.
+    pub fn check(&self, method: &Method, route: &str, principal: &Principal, business: Option<Uuid>) -> Result<(), ApiError> {
+        let Some(alternatives) = self.rules.get(&(method.clone(), route.to_string())) else {
+            // Routes and the document come from the same list, so this is a route added some other way.
+            return Err(ApiError::Internal(format!("no authorization rule for {method} {route}").into()));
+        };
+        if let Some(business) = business
+            && !principal.businesses.contains(&business)
+        {
+            return Err(ApiError::NotAMember(business));
+        }
+        match alternatives.first() {
+            Some(first) if !alternatives.iter().any(|scopes| scopes.is_subset(&principal.scopes)) => {
+                let missing: Vec<_> = first.difference(&principal.scopes).map(String::as_str).collect();
+                Err(ApiError::InsufficientScope(missing.join(" ")))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Middleware that checks the authenticated [`Principal`] against the [`Policy`] for the
+/// matched route and its `businessId`. It must run after authentication, which only lets a
+/// request through without a principal when authentication is not configured.
+pub async fn authorize(State(policy): State<Arc<Policy>>, mut request: Request, next: Next) -> Result<Response, ApiError> {
+    let Some(principal) = request.extensions().get::<Principal>().cloned() else {
+        return Ok(next.run(request).await);
+    };
+    let route = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string()).unwrap_or_default();
+    // A malformed id is left for the handler to reject as a bad path parameter.
+    let business = match request.extract_parts::<RawPathParams>().await {
+        Ok(params) => params.iter().find(|(name, _)| *name == BUSINESS_PARAMETER).and_then(|(_, id)| id.parse().ok()),
+        Err(_) => None,
+    };
+    policy.check(request.method(), &route, &principal, business)?;
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,631 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::error::SdkError;
+use aws_sdk_dynamodb::operation::update_item::UpdateItemError;
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
+use aws_sdk_dynamodb::types::{
+    AttributeDefinition, AttributeValue, BillingMode, Delete, KeySchemaElement, KeyType, Put,
+    ScalarAttributeType, TransactWriteItem,
+};
+use aws_sdk_dynamodb::Client;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::query::timestamp;
+use super::{ApiKeyRepository, BusinessRepository, RepositoryError, UserRepository};
+use crate::api_keys::{ApiKey, StoredApiKey};
+use crate::businesses::{Business, BusinessSettings};
+use crate::users::User;
+
+type Item = HashMap<String, AttributeValue>;
+
+/// Business, user and API key store backed by a single DynamoDB table.
+///
+/// Businesses live together at `PK = BUSINESSES`, `SK = BUSINESS#{businessId}`, so that they
+/// can be listed without a scan, with a `version` like users. Each owns a marker item at
+/// `PK = SLUG#{slug}`, `SK = SLUG` so that slugs stay unique.
+///
+/// Users live at `PK = BUSINESS#{businessId}`, `SK = USER#{userId}`. Each user owns a marker
+/// item at `SK = EMAIL#{email}` so that emails stay unique within the business, and a numeric
+/// `version` attribute guards updates against concurrent writers.
+///
+/// API keys live at `PK = APIKEY#{prefix}`, `SK = APIKEY`, where requests find them, with a
+/// copy without the secret hash at `PK = BUSINESS#{businessId}`, `SK = APIKEY#{prefix}` for
+/// listing.
+pub struct DynamoDbStore {
+    client: Client,
+    table: String,
+}
+
+impl DynamoDbStore {
+    pub fn new(client: Client, table: impl Into<String>) -> Self {
+        Self { client, table: table.into() }
+    }
+
+    /// Builds a client from the default AWS configuration, optionally pointed at another
+    /// endpoint such as DynamoDB Local.
+    pub async fn from_env(table: impl Into<String>, endpoint: Option<String>) -> Self {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+        if let Some(endpoint) = endpoint {
+            loader = loader.endpoint_url(endpoint);
+        }
+        Self::new(Client::new(&loader.load().await), table)
+    }
+
+    /// Creates the table with the key schema this repository expects.
+    pub async fn create_table(&self) -> Result<(), RepositoryError> {
+        let attribute = |name: &str| {
+            AttributeDefinition::builder()
+                .attribute_name(name)
+                .attribute_type(ScalarAttributeType::S)
+                .build()
+        };
+        let key = |name: &str, key_type| KeySchemaElement::builder().attribute_name(name).key_type(key_type).build();
+        self.client
+            .create_table()
+            .table_name(&self.table)
+            .attribute_definitions(attribute("PK").map_err(backend)?)
+            .attribute_definitions(attribute("SK").map_err(backend)?)
+            .key_schema(key("PK", KeyType::Hash).map_err(backend)?)
+            .key_schema(key("SK", KeyType::Range).map_err(backend)?)
+            .billing_mode(BillingMode::PayPerRequest)
+            .send()
+            .await
+            .map_err(backend)?;
+        Ok(())
+    }
+
+    async fn get_item(&self, business_id: Uuid, user_id: Uuid) -> Result<Option<Item>, RepositoryError> {
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .key("PK", partition_key(business_id))
+            .key("SK", user_key(user_id))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(backend)?;
+        Ok(output.item)
+    }
+
+    async fn get_business_item(&self, business_id: Uuid) -> Result<Option<Item>, RepositoryError> {
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .key("PK", AttributeValue::S("BUSINESSES".to_string()))
+            .key("SK", partition_key(business_id))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(backend)?;
+        Ok(output.item)
+    }
+
+    fn put_slug(&self, business: &Business) -> Result<TransactWriteItem, RepositoryError> {
+        let put = Put::builder()
+            .table_name(&self.table)
+            .item("PK", slug_key(&business.slug))
+            .item("SK", AttributeValue::S("SLUG".to_string()))
+            .item("uuid", AttributeValue::S(business.uuid.to_string()))
+            .condition_expression("attribute_not_exists(PK)")
+            .build()
+            .map_err(backend)?;
+        Ok(TransactWriteItem::builder().put(put).build())
+    }
+
+    fn put_email(&self, business_id: Uuid, user: &User) -> Result<TransactWriteItem, RepositoryError> {
+        let put = Put::builder()
+            .table_name(&self.table)
+            .item("PK", partition_key(business_id))
+            .item("SK", email_key(&user.email))
+            .item("uuid", AttributeValue::S(user.uuid.to_string()))
+            .condition_expression("attribute_not_exists(PK)")
+            .build()
+            .map_err(backend)?;
+        Ok(TransactWriteItem::builder().put(put).build())
+    }
+
+    fn delete_email(&self, business_id: Uuid, email: &str) -> Result<TransactWriteItem, RepositoryError> {
+        let delete = Delete::builder()
+            .table_name(&self.table)
+            .key("PK", partition_key(business_id))
+            .key("SK", email_key(email))
+            .build()
+            .map_err(backend)?;
+        Ok(TransactWriteItem::builder().delete(delete).build())
+    }
+
+    /// Runs a transaction, mapping each item's failed condition to the error it stands for.
+    async fn transact(
+        &self,
+        items: Vec<(TransactWriteItem, RepositoryError)>,
+    ) -> Result<(), RepositoryError> {
+        let (items, mut failures): (Vec<_>, Vec<_>) = items.into_iter().unzip();
+        let result = self
+            .client
+            .transact_write_items()
+            .set_transact_items(Some(items))
+            .send()
+            .await;
+        let Err(error) = result else {
+            return Ok(());
+        };
+        if let SdkError::ServiceError(service_error) = &error
+            && let TransactWriteItemsError::TransactionCanceledException(cancelled) = service_error.err()
+        {
+            let failed = cancelled
+                .cancellation_reasons()
+                .iter()
+                .position(|reason| reason.code() == Some("ConditionalCheckFailed"));
+            if let Some(index) = failed.filter(|index| *index < failures.len()) {
+                return Err(failures.swap_remove(index));
+            }
+        }
+        Err(backend(error))
+    }
+}
+
+fn backend(error: impl std::error::Error + Send + Sync + 'static) -> RepositoryError {
+    RepositoryError::Backend(Box::new(error))
+}
+
+/// Partition of a business's users, and the sort key of its own item under `BUSINESSES`.
+fn partition_key(business_id: Uuid) -> AttributeValue {
+    AttributeValue::S(format!("BUSINESS#{business_id}"))
+}
+
+fn slug_key(slug: &str) -> AttributeValue {
+    AttributeValue::S(format!("SLUG#{slug}"))
+}
+
+fn business_item(business: &Business, version: u64) -> Item {
+    let settings = HashMap::from([
+        ("timeZone".to_string(), AttributeValue::S(business.settings.time_zone.clone())),
+        ("locale".to_string(), AttributeValue::S(business.settings.locale.clone())),
+    ]);
+    HashMap::from([
+        ("PK".to_string(), AttributeValue::S("BUSINESSES".to_string())),
+        ("SK".to_string(), partition_key(business.uuid)),
+        ("uuid".to_string(), AttributeValue::S(business.uuid.to_string())),
+        ("name".to_string(), AttributeValue::S(business.name.clone())),
+        ("slug".to_string(), AttributeValue::S(business.slug.clone())),
+        ("status".to_string(), AttributeValue::S(business.status.as_str().to_string())),
+        ("settings".to_string(), AttributeValue::M(settings)),
+        ("createdAt".to_string(), AttributeValue::S(timestamp(&business.created_at))),
+        ("updatedAt".to_string(), AttributeValue::S(timestamp(&business.updated_at))),
+        ("version".to_string(), AttributeValue::N(version.to_string())),
+    ])
+}
+
+fn business(item: &Item) -> Result<Business, RepositoryError> {
+    let stored = attribute(item, "settings")?
+        .as_m()
+        .map_err(|_| RepositoryError::Backend("`settings` is not a map".into()))?;
+    // Settings added later are missing from older items and take their defaults.
+    let mut settings = BusinessSettings::default();
+    if stored.contains_key("timeZone") {
+        settings.time_zone = string(stored, "timeZone")?;
+    }
+    if stored.contains_key("locale") {
+        settings.locale = string(stored, "locale")?;
+    }
+    Ok(Business {
+        uuid: string(item, "uuid")?.parse().map_err(backend)?,
+        name: string(item, "name")?,
+        slug: string(item, "slug")?,
+        status: serde_json::from_value(string(item, "status")?.into()).map_err(backend)?,
+        settings,
+        created_at: string(item, "createdAt")?.parse().map_err(backend)?,
+        updated_at: string(item, "updatedAt")?.parse().map_err(backend)?,
+    })
+}
+
+fn user_key(user_id: Uuid) -> AttributeValue {
+    AttributeValue::S(format!("USER#{user_id}"))
+}
+
+/// Emails are unique ignoring ASCII case, like the other stores.
+fn email_key(email: &str) -> AttributeValue {
+    AttributeValue::S(format!("EMAIL#{}", email.to_ascii_lowercase()))
+}
+
+fn user_item(business_id: Uuid, user: &User, version: u64) -> Item {
+    let mut item = HashMap::from([
+        ("PK".to_string(), partition_key(business_id)),
+        ("SK".to_string(), user_key(user.uuid)),
+        ("uuid".to_string(), AttributeValue::S(user.uuid.to_string())),
+        ("firstName".to_string(), AttributeValue::S(user.first_name.clone())),
+        ("lastName".to_string(), AttributeValue::S(user.last_name.clone())),
+        ("email".to_string(), AttributeValue::S(user.email.clone())),
+        ("enabled".to_string(), AttributeValue::Bool(user.enabled)),
+        ("activated".to_string(), AttributeValue::Bool(user.activated)),
+        ("createdAt".to_string(), AttributeValue::S(timestamp(&user.created_at))),
+        ("version".to_string(), AttributeValue::N(version.to_string())),
+    ]);
+    if let Some(deleted_at) = user.deleted_at {
+        item.insert("deletedAt".to_string(), AttributeValue::S(timestamp(&deleted_at)));
+    }
+    item
+}
+
+fn attribute<'a>(item: &'a Item, name: &str) -> Result<&'a AttributeValue, RepositoryError> {
+    item.get(name)
+        .ok_or_else(|| RepositoryError::Backend(format!("item is missing `{name}`").into()))
+}
+
+fn string(item: &Item, name: &str) -> Result<String, RepositoryError> {
+    attribute(item, name)?
+        .as_s()
+        .cloned()
+        .map_err(|_| RepositoryError::Backend(format!("`{name}` is not a string").into()))
+}
+
+fn boolean(item: &Item, name: &str) -> Result<bool, RepositoryError> {
+    attribute(item, name)?
+        .as_bool()
+        .copied()
+        .map_err(|_| RepositoryError::Backend(format!("`{name}` is not a boolean").into()))
+}
+
+fn version(item: &Item) -> Result<u64, RepositoryError> {
+    attribute(item, "version")?
+        .as_n()
+        .ok()
+        .and_then(|version| version.parse().ok())
+        .ok_or_else(|| RepositoryError::Backend("`version` is not a number".into()))
+}
+
+fn user(item: &Item) -> Result<User, RepositoryError> {
+    Ok(User {
+        uuid: string(item, "uuid")?.parse().map_err(backend)?,
+        first_name: string(item, "firstName")?,
+        last_name: string(item, "lastName")?,
+        email: string(item, "email")?,
+        enabled: boolean(item, "enabled")?,
+        activated: boolean(item, "activated")?,
+        created_at: string(item, "createdAt")?.parse().map_err(backend)?,
+        deleted_at: optional_time(item, "deletedAt")?,
+    })
+}
+
+fn api_key_key(prefix: &str) -> AttributeValue {
+    AttributeValue::S(format!("APIKEY#{prefix}"))
+}
+
+fn api_key_item(key: &ApiKey) -> Item {
+    let mut item = HashMap::from([
+        ("prefix".to_string(), AttributeValue::S(key.prefix.clone())),
+        ("name".to_string(), AttributeValue::S(key.name.clone())),
+        ("scopes".to_string(), AttributeValue::L(key.scopes.iter().map(|scope| AttributeValue::S(scope.as_str().to_string())).collect())),
+        ("createdAt".to_string(), AttributeValue::S(timestamp(&key.created_at))),
+    ]);
+    if let Some(expires_at) = key.expires_at {
+        item.insert("expiresAt".to_string(), AttributeValue::S(timestamp(&expires_at)));
+    }
+    item
+}
+
+fn optional_time(item: &Item, name: &str) -> Result<Option<DateTime<Utc>>, RepositoryError> {
+    match item.get(name) {
+        Some(_) => Ok(Some(string(item, name)?.parse().map_err(backend)?)),
+        None => Ok(None),
+    }
+}
+
+fn api_key(item: &Item) -> Result<ApiKey, RepositoryError> {
+    let scopes = attribute(item, "scopes")?
+        .as_l()
+        .map_err(|_| RepositoryError::Backend("`scopes` is not a list".into()))?
+        .iter()
+        .map(|scope| serde_json::from_value(serde_json::Value::String(scope.as_s().cloned().unwrap_or_default())))
+        .collect::<Result<_, _>>()
+        .map_err(backend)?;
+    Ok(ApiKey {
+        prefix: string(item, "prefix")?,
+        name: string(item, "name")?,
+        scopes,
+        created_at: string(item, "createdAt")?.parse().map_err(backend)?,
+        expires_at: optional_time(item, "expiresAt")?,
+        last_used_at: optional_time(item, "lastUsedAt")?,
+    })
+}
+
+#[async_trait]
+impl BusinessRepository for DynamoDbStore {
+    async fn get_business(&self, business_id: Uuid) -> Result<Option<Business>, RepositoryError> {
+        self.get_business_item(business_id).await?.as_ref().map(business).transpose()
+    }
+
+    async fn list_businesses(&self) -> Result<Vec<Business>, RepositoryError> {
+        let mut businesses = Vec::new();
+        let mut start_key = None;
+        loop {
+            let output = self
+                .client
+                .query()
+                .table_name(&self.table)
+                .key_condition_expression("PK = :pk AND begins_with(SK, :sk)")
+                .expression_attribute_values(":pk", AttributeValue::S("BUSINESSES".to_string()))
+                .expression_attribute_values(":sk", AttributeValue::S("BUSINESS#".to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(backend)?;
+            for item in output.items() {
+                businesses.push(business(item)?);
+            }
+            start_key = output.last_evaluated_key;
+            if start_key.is_none() {
+                return Ok(businesses);
+            }
+        }
+    }
+
+    async fn create_business(&self, business: Business) -> Result<Business, RepositoryError> {
+        let put = Put::builder()
+            .table_name(&self.table)
+            .set_item(Some(business_item(&business, 1)))
+            .condition_expression("attribute_not_exists(PK)")
+            .build()
+            .map_err(backend)?;
+        self.transact(vec![
+            (TransactWriteItem::builder().put(put).build(), RepositoryError::Conflict),
+            (self.put_slug(&business)?, RepositoryError::SlugTaken),
+        ])
+        .await?;
+        Ok(business)
+    }
+
+    async fn update_business(&self, business: Business) -> Result<Business, RepositoryError> {
+        let current = self
+            .get_business_item(business.uuid)
+            .await?
+            .ok_or(RepositoryError::NotFound)?;
+        let expected = version(&current)?;
+        let put = Put::builder()
+            .table_name(&self.table)
+            .set_item(Some(business_item(&business, expected + 1)))
+            .condition_expression("version = :expected")
+            .expression_attribute_values(":expected", AttributeValue::N(expected.to_string()))
+            .build()
+            .map_err(backend)?;
+        let mut items = vec![(TransactWriteItem::builder().put(put).build(), RepositoryError::ConcurrentUpdate)];
+
+        let previous_slug = string(&current, "slug")?;
+        if previous_slug != business.slug {
+            let delete = Delete::builder()
+                .table_name(&self.table)
+                .key("PK", slug_key(&previous_slug))
+                .key("SK", AttributeValue::S("SLUG".to_string()))
+                .build()
+                .map_err(backend)?;
+            items.push((self.put_slug(&business)?, RepositoryError::SlugTaken));
+            items.push((TransactWriteItem::builder().delete(delete).build(), RepositoryError::ConcurrentUpdate));
+        }
+        self.transact(items).await?;
+        Ok(business)
+    }
+}
+
+#[async_trait]
+impl UserRepository for DynamoDbStore {
+    async fn get(&self, business_id: Uuid, user_id: Uuid) -> Result<Option<User>, RepositoryError> {
+        self.get_item(business_id, user_id).await?.as_ref().map(user).transpose()
+    }
+
+    async fn list(&self, business_id: Uuid) -> Result<Vec<User>, RepositoryError> {
+        let mut users = Vec::new();
+        let mut start_key = None;
+        loop {
+            let output = self
+                .client
+                .query()
+                .table_name(&self.table)
+                .key_condition_expression("PK = :pk AND begins_with(SK, :sk)")
+                .expression_attribute_values(":pk", partition_key(business_id))
+                .expression_attribute_values(":sk", AttributeValue::S("USER#".to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(backend)?;
+            for item in output.items() {
+                users.push(user(item)?);
+            }
+            start_key = output.last_evaluated_key;
+            if start_key.is_none() {
+                return Ok(users);
+            }
+        }
+    }
+
+    async fn create(&self, business_id: Uuid, user: User) -> Result<User, RepositoryError> {
+        let put = Put::builder()
+            .table_name(&self.table)
+            .set_item(Some(user_item(business_id, &user, 1)))
+            .condition_expression("attribute_not_exists(PK)")
+            .build()
+            .map_err(backend)?;
+        self.transact(vec![
+            (TransactWriteItem::builder().put(put).build(), RepositoryError::Conflict),
+            (self.put_email(business_id, &user)?, RepositoryError::EmailTaken),
+        ])
+        .await?;
+        Ok(user)
+    }
+
+    async fn update(&self, business_id: Uuid, user: User, expected: &User) -> Result<User, RepositoryError> {
+        let current = self
+            .get_item(business_id, user.uuid)
+            .await?
+            .ok_or(RepositoryError::NotFound)?;
+        // The version read here only guards the write below; comparing with what the caller
+        // read covers the time since then.
+        if self::user(&current)? != *expected {
+            return Err(RepositoryError::ConcurrentUpdate);
+        }
+        let expected = version(&current)?;
+        let put = Put::builder()
+            .table_name(&self.table)
+            .set_item(Some(user_item(business_id, &user, expected + 1)))
+            .condition_expression("version = :expected")
+            .expression_attribute_values(":expected", AttributeValue::N(expected.to_string()))
+            .build()
+            .map_err(backend)?;
+        let mut items = vec![(TransactWriteItem::builder().put(put).build(), RepositoryError::ConcurrentUpdate)];
+
+        let previous_email = string(&current, "email")?;
+        if email_key(&previous_email) != email_key(&user.email) {
+            items.push((self.put_email(business_id, &user)?, RepositoryError::EmailTaken));
+            items.push((self.delete_email(business_id, &previous_email)?, RepositoryError::ConcurrentUpdate));
+        }
+        self.transact(items).await?;
+        Ok(user)
+    }
+
+    async fn delete(&self, business_id: Uuid, user_id: Uuid) -> Result<(), RepositoryError> {
+        let current = self
+            .get_item(business_id, user_id)
+            .await?
+            .ok_or(RepositoryError::NotFound)?;
+        let delete = Delete::builder()
+            .table_name(&self.table)
+            .key("PK", partition_key(business_id))
+            .key("SK", user_key(user_id))
+            .condition_expression("version = :expected")
+            .expression_attribute_values(":expected", AttributeValue::N(version(&current)?.to_string()))
+            .build()
+            .map_err(backend)?;
+        self.transact(vec![
+            (TransactWriteItem::builder().delete(delete).build(), RepositoryError::ConcurrentUpdate),
+            (self.delete_email(business_id, &string(&current, "email")?)?, RepositoryError::ConcurrentUpdate),
+        ])
+        .await
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for DynamoDbStore {
+    async fn find_key(&self, prefix: &str) -> Result<Option<StoredApiKey>, RepositoryError> {
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .key("PK", api_key_key(prefix))
+            .key("SK", AttributeValue::S("APIKEY".to_string()))
+            .send()
+            .await
+            .map_err(backend)?;
+        let Some(item) = output.item else {
+            return Ok(None);
+        };
+        Ok(Some(StoredApiKey {
+            business_id: string(&item, "businessId")?.parse().map_err(backend)?,
+            api_key: api_key(&item)?,
+            secret_hash: string(&item, "secretHash")?,
+        }))
+    }
+
+    async fn list_keys(&self, business_id: Uuid) -> Result<Vec<ApiKey>, RepositoryError> {
+        let mut keys = Vec::new();
+        let mut start_key = None;
+        loop {
+            let output = self
+                .client
+                .query()
+                .table_name(&self.table)
+                .key_condition_expression("PK = :pk AND begins_with(SK, :sk)")
+                .expression_attribute_values(":pk", partition_key(business_id))
+                .expression_attribute_values(":sk", AttributeValue::S("APIKEY#".to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(backend)?;
+            for item in output.items() {
+                keys.push(api_key(item)?);
+            }
+            start_key = output.last_evaluated_key;
+            if start_key.is_none() {
+                return Ok(keys);
+            }
+        }
+    }
+
+    async fn create_key(&self, key: StoredApiKey) -> Result<(), RepositoryError> {
+        let mut lookup = api_key_item(&key.api_key);
+        lookup.insert("PK".to_string(), api_key_key(&key.api_key.prefix));
+        lookup.insert("SK".to_string(), AttributeValue::S("APIKEY".to_string()));
+        lookup.insert("businessId".to_string(), AttributeValue::S(key.business_id.to_string()));
+        lookup.insert("secretHash".to_string(), AttributeValue::S(key.secret_hash.clone()));
+        let mut listed = api_key_item(&key.api_key);
+        listed.insert("PK".to_string(), partition_key(key.business_id));
+        listed.insert("SK".to_string(), api_key_key(&key.api_key.prefix));
+
+        let put = |item| {
+            Put::builder()
+                .table_name(&self.table)
+                .set_item(Some(item))
+                .condition_expression("attribute_not_exists(PK)")
+                .build()
+                .map(|put| TransactWriteItem::builder().put(put).build())
+                .map_err(backend)
+        };
+        self.transact(vec![(put(lookup)?, RepositoryError::Conflict), (put(listed)?, RepositoryError::Conflict)])
+            .await
+    }
+
+    async fn touch_key(&self, prefix: &str, used_at: DateTime<Utc>) -> Result<(), RepositoryError> {
+        let found = self.find_key(prefix).await?.ok_or(RepositoryError::NotFound)?;
+        let keys = [
+            (api_key_key(prefix), AttributeValue::S("APIKEY".to_string())),
+            (partition_key(found.business_id), api_key_key(prefix)),
+        ];
+        for (partition, sort) in keys {
+            let result = self
+                .client
+                .update_item()
+                .table_name(&self.table)
+                .key("PK", partition)
+                .key("SK", sort)
+                .update_expression("SET lastUsedAt = :used")
+                .condition_expression("attribute_exists(PK)")
+                .expression_attribute_values(":used", AttributeValue::S(timestamp(&used_at)))
+                .send()
+                .await;
+            match result {
+                Ok(_) => {}
+                Err(SdkError::ServiceError(error)) if matches!(error.err(), UpdateItemError::ConditionalCheckFailedException(_)) => {
+                    return Err(RepositoryError::NotFound);
+                }
+                Err(error) => return Err(backend(error)),
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_key(&self, business_id: Uuid, prefix: &str) -> Result<(), RepositoryError> {
+        // The lookup item's business guards against revoking another business's key.
+        let lookup = Delete::builder()
+            .table_name(&self.table)
+            .key("PK", api_key_key(prefix))
+            .key("SK", AttributeValue::S("APIKEY".to_string()))
+            .condition_expression("businessId = :business")
+            .expression_attribute_values(":business", AttributeValue::S(business_id.to_string()))
+            .build()
+            .map_err(backend)?;
+        let listed = Delete::builder()
+            .table_name(&self.table)
+            .key("PK", partition_key(business_id))
+            .key("SK", api_key_key(prefix))
+            .condition_expression("attribute_exists(PK)")
+            .build()
+            .map_err(backend)?;
+        self.transact(vec![
+            (TransactWriteItem::builder().delete(lookup).build(), RepositoryError::NotFound),
+            (TransactWriteItem::builder().delete(listed).build(), RepositoryError::NotFound),
+        ])
+        .await
+    }
+}
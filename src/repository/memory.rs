@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::{ApiKeyRepository, BusinessRepository, RepositoryError, UserRepository};
+use crate::api_keys::{ApiKey, StoredApiKey};
+use crate::businesses::Business;
+use crate::users::User;
+
+/// Thread-safe business, user and API key store kept in process memory.
+#[derive(Default)]
+pub struct InMemoryStore {
+    businesses: RwLock<BTreeMap<Uuid, Business>>,
+    users: RwLock<HashMap<Uuid, BTreeMap<Uuid, User>>>,
+    api_keys: RwLock<BTreeMap<String, StoredApiKey>>,
+}
+
+/// A fixture record: a user plus the business it belongs to.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SeedUser {
+    business_id: Uuid,
+    #[serde(flatten)]
+    user: User,
+}
+
+impl InMemoryStore {
+    /// Seeds a store from a JSON array of users, each carrying a `businessId`. Each business
+    /// named gets a [placeholder](Business::placeholder) created with its earliest user.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let seed: Vec<SeedUser> = serde_json::from_str(json)?;
+        let mut businesses = BTreeMap::new();
+        let mut users: HashMap<Uuid, BTreeMap<Uuid, User>> = HashMap::new();
+        for SeedUser { business_id, user } in seed {
+            let business = businesses
+                .entry(business_id)
+                .or_insert_with(|| Business::placeholder(business_id, user.created_at));
+            business.created_at = business.created_at.min(user.created_at);
+            business.updated_at = business.created_at;
+            users.entry(business_id).or_default().insert(user.uuid, user);
+        }
+        Ok(Self { businesses: RwLock::new(businesses), users: RwLock::new(users), api_keys: RwLock::default() })
+    }
+}
+
+/// Emails are unique per business, ignoring case, matching the SQLite `NOCASE` constraint.
+fn email_taken(users: &BTreeMap<Uuid, User>, user: &User) -> bool {
+    users
+        .values()
+        .any(|other| other.uuid != user.uuid && other.email.eq_ignore_ascii_case(&user.email))
+}
+
+/// Slugs are unique across businesses.
+fn slug_taken(businesses: &BTreeMap<Uuid, Business>, business: &Business) -> bool {
+    businesses.values().any(|other| other.uuid != business.uuid && other.slug == business.slug)
+}
+
+#[async_trait]
+impl BusinessRepository for InMemoryStore {
+    async fn get_business(&self, business_id: Uuid) -> Result<Option<Business>, RepositoryError> {
+        Ok(self.businesses.read().unwrap().get(&business_id).cloned())
+    }
+
+    async fn list_businesses(&self) -> Result<Vec<Business>, RepositoryError> {
+        Ok(self.businesses.read().unwrap().values().cloned().collect())
+    }
+
+    async fn create_business(&self, business: Business) -> Result<Business, RepositoryError> {
+        let mut businesses = self.businesses.write().unwrap();
+        if businesses.contains_key(&business.uuid) {
+            return Err(RepositoryError::Conflict);
+        }
+        if slug_taken(&businesses, &business) {
+            return Err(RepositoryError::SlugTaken);
+        }
+        businesses.insert(business.uuid, business.clone());
+        Ok(business)
+    }
+
+    async fn update_business(&self, business: Business) -> Result<Business, RepositoryError> {
+        let mut businesses = self.businesses.write().unwrap();
+        if !businesses.contains_key(&business.uuid) {
+            return Err(RepositoryError::NotFound);
+        }
+        if slug_taken(&businesses, &business) {
+            return Err(RepositoryError::SlugTaken);
+        }
+        businesses.insert(business.uuid, business.clone());
+        Ok(business)
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryStore {
+    async fn get(&self, business_id: Uuid, user_id: Uuid) -> Result<Option<User>, RepositoryError> {
+        let users = self.users.read().unwrap();
+        Ok(users.get(&business_id).and_then(|users| users.get(&user_id)).cloned())
+    }
+
+    async fn list(&self, business_id: Uuid) -> Result<Vec<User>, RepositoryError> {
+        let users = self.users.read().unwrap();
+        Ok(users
+            .get(&business_id)
+            .map(|users| users.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn create(&self, business_id: Uuid, user: User) -> Result<User, RepositoryError> {
+        let mut users = self.users.write().unwrap();
+        let users = users.entry(business_id).or_default();
+        if users.contains_key(&user.uuid) {
+            return Err(RepositoryError::Conflict);
+        }
+        if email_taken(users, &user) {
+            return Err(RepositoryError::EmailTaken);
+        }
+        users.insert(user.uuid, user.clone());
+        Ok(user)
+    }
+
+    async fn update(&self, business_id: Uuid, user: User, expected: &User) -> Result<User, RepositoryError> {
+        let mut users = self.users.write().unwrap();
+        let users = users
+            .get_mut(&business_id)
+            .filter(|users| users.contains_key(&user.uuid))
+            .ok_or(RepositoryError::NotFound)?;
+        if users.get(&user.uuid) != Some(expected) {
+            return Err(RepositoryError::ConcurrentUpdate);
+        }
+        if email_taken(users, &user) {
+            return Err(RepositoryError::EmailTaken);
+        }
+        users.insert(user.uuid, user.clone());
+        Ok(user)
+    }
+
+    async fn delete(&self, business_id: Uuid, user_id: Uuid) -> Result<(), RepositoryError> {
+        let mut users = self.users.write().unwrap();
+        users
+            .get_mut(&business_id)
+            .and_then(|users| users.remove(&user_id))
+            .map(|_| ())
+            .ok_or(RepositoryError::NotFound)
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for InMemoryStore {
+    async fn find_key(&self, prefix: &str) -> Result<Option<StoredApiKey>, RepositoryError> {
+        Ok(self.api_keys.read().unwrap().get(prefix).cloned())
+    }
+
+    async fn list_keys(&self, business_id: Uuid) -> Result<Vec<ApiKey>, RepositoryError> {
+        let api_keys = self.api_keys.read().unwrap();
+        Ok(api_keys
+            .values()
+            .filter(|key| key.business_id == business_id)
+            .map(|key| key.api_key.clone())
+            .collect())
+    }
+
+    async fn create_key(&self, key: StoredApiKey) -> Result<(), RepositoryError> {
+        let mut api_keys = self.api_keys.write().unwrap();
+        if api_keys.contains_key(&key.api_key.prefix) {
+            return Err(RepositoryError::Conflict);
+        }
+        api_keys.insert(key.api_key.prefix.clone(), key);
+        Ok(())
+    }
+
+    async fn touch_key(&self, prefix: &str, used_at: DateTime<Utc>) -> Result<(), RepositoryError> {
+        let mut api_keys = self.api_keys.write().unwrap();
+        let key = api_keys.get_mut(prefix).ok_or(RepositoryError::NotFound)?;
+        key.api_key.last_used_at = Some(used_at);
+        Ok(())
+    }
+
+    async fn delete_key(&self, business_id: Uuid, prefix: &str) -> Result<(), RepositoryError> {
+        let mut api_keys = self.api_keys.write().unwrap();
+        if api_keys.get(prefix).is_none_or(|key| key.business_id != business_id) {
+            return Err(RepositoryError::NotFound);
+        }
+        api_keys.remove(prefix);
+        Ok(())
+    }
+}
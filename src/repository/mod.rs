@@ -0,0 +1,105 @@
+//! Storage for businesses and everything under them. Each store implements every trait here
+//! and holds all of a business's resources.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::api_keys::{ApiKey, StoredApiKey};
+use crate::businesses::Business;
+use crate::users::User;
+
+pub mod dynamodb;
+pub mod memory;
+pub mod query;
+pub mod sqlite;
+
+pub use query::UserQuery;
+
+/// Errors raised by a store.
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoryError {
+    #[error("not found")]
+    NotFound,
+    #[error("already exists")]
+    Conflict,
+    #[error("email is already in use within the business")]
+    EmailTaken,
+    #[error("slug is already in use by another business")]
+    SlugTaken,
+    #[error("record was modified concurrently, retry the request")]
+    ConcurrentUpdate,
+    #[error("invalid record: {0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Storage for businesses, which users and API keys belong to.
+#[async_trait]
+pub trait BusinessRepository: Send + Sync {
+    /// Returns the business, archived or not.
+    async fn get_business(&self, business_id: Uuid) -> Result<Option<Business>, RepositoryError>;
+
+    /// Returns every business, archived or not, ordered by uuid.
+    async fn list_businesses(&self) -> Result<Vec<Business>, RepositoryError>;
+
+    /// Stores a new business, failing with `Conflict` if the uuid is taken
+    /// and `SlugTaken` if another business has the same slug.
+    async fn create_business(&self, business: Business) -> Result<Business, RepositoryError>;
+
+    /// Replaces an existing business, failing with `NotFound` if there is none
+    /// and `SlugTaken` if another business has the same slug.
+    async fn update_business(&self, business: Business) -> Result<Business, RepositoryError>;
+}
+
+/// Storage for user accounts; every operation is scoped to a business.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Returns the user, or `None` if the business has no such user.
+    async fn get(&self, business_id: Uuid, user_id: Uuid) -> Result<Option<User>, RepositoryError>;
+
+    /// Returns every user in the business, including soft-deleted ones, ordered by uuid.
+    async fn list(&self, business_id: Uuid) -> Result<Vec<User>, RepositoryError>;
+
+    /// Returns live users matching the query in its order, up to `query.limit + 1` of them.
+    ///
+    /// The default filters the whole of [`list`](Self::list) in process; stores that can
+    /// filter and order natively should override it.
+    async fn query(&self, business_id: Uuid, query: &UserQuery) -> Result<Vec<User>, RepositoryError> {
+        Ok(query.apply(self.list(business_id).await?))
+    }
+
+    /// Stores a new user, failing with `Conflict` if the uuid is taken
+    /// and `EmailTaken` if another user in the business has the same email.
+    async fn create(&self, business_id: Uuid, user: User) -> Result<User, RepositoryError>;
+
+    /// Replaces an existing user read as `expected`, failing with `NotFound` if there is none,
+    /// `EmailTaken` if another user in the business has the same email and `ConcurrentUpdate`
+    /// if the stored user no longer matches `expected`, so that a change made since it was
+    /// read is not silently overwritten.
+    async fn update(&self, business_id: Uuid, user: User, expected: &User) -> Result<User, RepositoryError>;
+
+    /// Removes a user, failing with `NotFound` if there is none.
+    async fn delete(&self, business_id: Uuid, user_id: Uuid) -> Result<(), RepositoryError>;
+}
+
+/// Storage for API keys. Keys belong to a business but are found by prefix alone, since
+/// that is all a request carries.
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    /// Returns the key with this prefix, whichever business it belongs to.
+    async fn find_key(&self, prefix: &str) -> Result<Option<StoredApiKey>, RepositoryError>;
+
+    /// Returns every key in the business, ordered by prefix.
+    async fn list_keys(&self, business_id: Uuid) -> Result<Vec<ApiKey>, RepositoryError>;
+
+    /// Stores a new key, failing with `Conflict` if its prefix is taken.
+    async fn create_key(&self, key: StoredApiKey) -> Result<(), RepositoryError>;
+
+    /// Records when the key was last used, failing with `NotFound` if there is none.
+    async fn touch_key(&self, prefix: &str, used_at: DateTime<Utc>) -> Result<(), RepositoryError>;
+
+    /// Removes a key, failing with `NotFound` if the business has none with this prefix.
+    async fn delete_key(&self, business_id: Uuid, prefix: &str) -> Result<(), RepositoryError>;
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::users::User;
+
+/// Field users are listed by; ties are broken by uuid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum UserSort {
+    /// Last name, then first name, ignoring ASCII case.
+    #[default]
+    Name,
+    /// Creation time.
+    CreatedAt,
+}
+
+impl UserSort {
+    /// Number of values in a sort key.
+    pub fn key_len(self) -> usize {
+        match self {
+            Self::Name => 3,
+            Self::CreatedAt => 2,
+        }
+    }
+}
+
+/// Direction of a sort.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Which users to list and in what order.
+///
+/// Pages are keyed on the sort key of the last user returned rather than on an offset or a
+/// backend token, so a cursor from one store resumes at the same place in any other.
+#[derive(Clone, Debug)]
+pub struct UserQuery {
+    pub enabled: Option<bool>,
+    pub activated: Option<bool>,
+    /// Matched against the start of the email, ignoring ASCII case.
+    pub email_prefix: Option<String>,
+    pub sort: UserSort,
+    pub order: SortOrder,
+    /// Sort key of the last user on the previous page.
+    pub after: Option<Vec<String>>,
+    pub limit: usize,
+}
+
+/// Formats timestamps with a fixed width so that they compare correctly as text.
+pub fn timestamp(time: &DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string()
+}
+
+/// The values users are ordered by, compared as strings.
+pub fn sort_key(user: &User, sort: UserSort) -> Vec<String> {
+    match sort {
+        UserSort::Name => vec![
+            user.last_name.to_ascii_lowercase(),
+            user.first_name.to_ascii_lowercase(),
+            user.uuid.to_string(),
+        ],
+        UserSort::CreatedAt => vec![timestamp(&user.created_at), user.uuid.to_string()],
+    }
+}
+
+impl UserQuery {
+    /// Whether a live user passes the filters.
+    pub fn matches(&self, user: &User) -> bool {
+        user.deleted_at.is_none()
+            && self.enabled.is_none_or(|enabled| user.enabled == enabled)
+            && self.activated.is_none_or(|activated| user.activated == activated)
+            && self.email_prefix.as_ref().is_none_or(|prefix| {
+                user.email.to_ascii_lowercase().starts_with(&prefix.to_ascii_lowercase())
+            })
+    }
+
+    /// Filters, sorts and pages users in process, returning up to `limit + 1` of them
+    /// so that the caller can tell whether another page follows.
+    pub fn apply(&self, users: Vec<User>) -> Vec<User> {
+        let mut users: Vec<(Vec<String>, User)> = users
+            .into_iter()
+            .filter(|user| self.matches(user))
+            .map(|user| (sort_key(&user, self.sort), user))
+            .filter(|(key, _)| match (&self.after, self.order) {
+                (None, _) => true,
+                (Some(after), SortOrder::Asc) => key > after,
+                (Some(after), SortOrder::Desc) => key < after,
+            })
+            .collect();
+        users.sort_by(|(a, _), (b, _)| match self.order {
+            SortOrder::Asc => a.cmp(b),
+            SortOrder::Desc => b.cmp(a),
+        });
+        users.into_iter().take(self.limit + 1).map(|(_, user)| user).collect()
+    }
+}
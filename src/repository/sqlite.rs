@@ -0,0 +1,372 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::error::ErrorKind;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::{QueryBuilder, Row, Sqlite};
+use uuid::fmt::Hyphenated;
+use uuid::Uuid;
+
+use super::query::{timestamp, SortOrder, UserSort};
+use super::{ApiKeyRepository, BusinessRepository, RepositoryError, UserQuery, UserRepository};
+use crate::api_keys::{ApiKey, StoredApiKey};
+use crate::businesses::Business;
+use crate::users::User;
+
+/// Migrations under `migrations/`, embedded at compile time.
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!();
+
+/// Business, user and API key store backed by SQLite.
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// Opens (creating if needed) the database at `url` and runs pending migrations.
+    pub async fn connect(url: &str) -> Result<Self, RepositoryError> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let mut pool = SqlitePoolOptions::new();
+        // Every connection to `sqlite::memory:` is a separate database, so keep exactly one alive.
+        if options.get_filename().as_os_str() == ":memory:" {
+            pool = pool.max_connections(1).idle_timeout(None).max_lifetime(None);
+        }
+        Self::new(pool.connect_with(options).await?).await
+    }
+
+    /// Wraps an existing pool, running pending migrations first.
+    pub async fn new(pool: SqlitePool) -> Result<Self, RepositoryError> {
+        MIGRATOR.run(&pool).await.map_err(|error| RepositoryError::Backend(error.into()))?;
+        Ok(Self { pool })
+    }
+}
+
+fn business(row: SqliteRow) -> Result<Business, sqlx::Error> {
+    Ok(Business {
+        uuid: row.try_get::<Hyphenated, _>("uuid")?.into_uuid(),
+        name: row.try_get("name")?,
+        slug: row.try_get("slug")?,
+        status: serde_json::from_value(row.try_get::<String, _>("status")?.into())
+            .map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+        // A JSON object of settings.
+        settings: serde_json::from_str(row.try_get("settings")?).map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+        created_at: row
+            .try_get::<String, _>("created_at")?
+            .parse()
+            .map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+        updated_at: row
+            .try_get::<String, _>("updated_at")?
+            .parse()
+            .map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+    })
+}
+
+fn user(row: SqliteRow) -> Result<User, sqlx::Error> {
+    Ok(User {
+        uuid: row.try_get::<Hyphenated, _>("uuid")?.into_uuid(),
+        first_name: row.try_get("first_name")?,
+        last_name: row.try_get("last_name")?,
+        email: row.try_get("email")?,
+        enabled: row.try_get("enabled")?,
+        activated: row.try_get("activated")?,
+        created_at: row
+            .try_get::<String, _>("created_at")?
+            .parse()
+            .map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+        deleted_at: row.try_get("deleted_at")?,
+    })
+}
+
+fn api_key(row: &SqliteRow) -> Result<ApiKey, sqlx::Error> {
+    Ok(ApiKey {
+        prefix: row.try_get("prefix")?,
+        name: row.try_get("name")?,
+        // A JSON array of scope names.
+        scopes: serde_json::from_str(row.try_get("scopes")?).map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+        created_at: row
+            .try_get::<String, _>("created_at")?
+            .parse()
+            .map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+        expires_at: row.try_get("expires_at")?,
+        last_used_at: row.try_get("last_used_at")?,
+    })
+}
+
+fn stored_api_key(row: SqliteRow) -> Result<StoredApiKey, sqlx::Error> {
+    Ok(StoredApiKey {
+        business_id: row.try_get::<Hyphenated, _>("business_id")?.into_uuid(),
+        api_key: api_key(&row)?,
+        secret_hash: row.try_get("secret_hash")?,
+    })
+}
+
+impl From<sqlx::Error> for RepositoryError {
+    fn from(error: sqlx::Error) -> Self {
+        let Some(database_error) = error.as_database_error() else {
+            return Self::Backend(error.into());
+        };
+        match database_error.kind() {
+            ErrorKind::UniqueViolation if database_error.message().contains(".email") => {
+                Self::EmailTaken
+            }
+            ErrorKind::UniqueViolation if database_error.message().contains(".slug") => Self::SlugTaken,
+            ErrorKind::UniqueViolation => Self::Conflict,
+            ErrorKind::NotNullViolation | ErrorKind::CheckViolation => {
+                Self::Invalid(database_error.message().to_string())
+            }
+            _ => Self::Backend(error.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl BusinessRepository for SqliteStore {
+    async fn get_business(&self, business_id: Uuid) -> Result<Option<Business>, RepositoryError> {
+        let row = sqlx::query("SELECT * FROM businesses WHERE uuid = ?")
+            .bind(business_id.hyphenated())
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(business).transpose()?)
+    }
+
+    async fn list_businesses(&self) -> Result<Vec<Business>, RepositoryError> {
+        let rows = sqlx::query("SELECT * FROM businesses ORDER BY uuid").fetch_all(&self.pool).await?;
+        Ok(rows.into_iter().map(business).collect::<Result<_, _>>()?)
+    }
+
+    async fn create_business(&self, business: Business) -> Result<Business, RepositoryError> {
+        let settings = serde_json::to_string(&business.settings).map_err(|error| RepositoryError::Backend(error.into()))?;
+        let result = sqlx::query(
+            "INSERT INTO businesses (uuid, name, slug, status, settings, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(business.uuid.hyphenated())
+        .bind(&business.name)
+        .bind(&business.slug)
+        .bind(business.status.as_str())
+        .bind(settings)
+        .bind(timestamp(&business.created_at))
+        .bind(timestamp(&business.updated_at))
+        .execute(&self.pool)
+        .await;
+        match result.map_err(RepositoryError::from) {
+            Ok(_) => Ok(business),
+            // As with users' emails, a taken uuid wins over a taken slug.
+            Err(RepositoryError::SlugTaken) if self.get_business(business.uuid).await?.is_some() => {
+                Err(RepositoryError::Conflict)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    async fn update_business(&self, business: Business) -> Result<Business, RepositoryError> {
+        let settings = serde_json::to_string(&business.settings).map_err(|error| RepositoryError::Backend(error.into()))?;
+        let result = sqlx::query(
+            "UPDATE businesses SET name = ?, slug = ?, status = ?, settings = ?, updated_at = ? WHERE uuid = ?",
+        )
+        .bind(&business.name)
+        .bind(&business.slug)
+        .bind(business.status.as_str())
+        .bind(settings)
+        .bind(timestamp(&business.updated_at))
+        .bind(business.uuid.hyphenated())
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(business)
+    }
+}
+
+#[async_trait]
+impl UserRepository for SqliteStore {
+    async fn get(&self, business_id: Uuid, user_id: Uuid) -> Result<Option<User>, RepositoryError> {
+        let row = sqlx::query("SELECT * FROM users WHERE business_id = ? AND uuid = ?")
+            .bind(business_id.hyphenated())
+            .bind(user_id.hyphenated())
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(user).transpose()?)
+    }
+
+    async fn list(&self, business_id: Uuid) -> Result<Vec<User>, RepositoryError> {
+        let rows = sqlx::query("SELECT * FROM users WHERE business_id = ? ORDER BY uuid")
+            .bind(business_id.hyphenated())
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(user).collect::<Result<_, _>>()?)
+    }
+
+    async fn query(&self, business_id: Uuid, query: &UserQuery) -> Result<Vec<User>, RepositoryError> {
+        let mut sql = QueryBuilder::<Sqlite>::new("SELECT * FROM users WHERE deleted_at IS NULL AND business_id = ");
+        sql.push_bind(business_id.hyphenated());
+        if let Some(enabled) = query.enabled {
+            sql.push(" AND enabled = ").push_bind(enabled);
+        }
+        if let Some(activated) = query.activated {
+            sql.push(" AND activated = ").push_bind(activated);
+        }
+        if let Some(prefix) = &query.email_prefix {
+            let pattern = prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            sql.push(" AND email LIKE ").push_bind(format!("{pattern}%")).push(" ESCAPE '\\'");
+        }
+
+        // Must order exactly like `sort_key`: `lower` only folds ASCII, as does the in-process key.
+        let columns: &[&str] = match query.sort {
+            UserSort::Name => &["lower(last_name)", "lower(first_name)", "uuid"],
+            UserSort::CreatedAt => &["created_at", "uuid"],
+        };
+        let (comparison, direction) = match query.order {
+            SortOrder::Asc => (">", "ASC"),
+            SortOrder::Desc => ("<", "DESC"),
+        };
+        if let Some(after) = &query.after {
+            sql.push(format!(" AND ({}) {comparison} (", columns.join(", ")));
+            let mut values = sql.separated(", ");
+            for value in after {
+                values.push_bind(value.as_str());
+            }
+            sql.push(")");
+        }
+        let order_by: Vec<String> = columns.iter().map(|column| format!("{column} {direction}")).collect();
+        sql.push(" ORDER BY ").push(order_by.join(", "));
+        sql.push(" LIMIT ").push_bind(query.limit as i64 + 1);
+
+        let rows = sql.build().fetch_all(&self.pool).await?;
+        Ok(rows.into_iter().map(user).collect::<Result<_, _>>()?)
+    }
+
+    async fn create(&self, business_id: Uuid, user: User) -> Result<User, RepositoryError> {
+        let result = sqlx::query(
+            "INSERT INTO users (business_id, uuid, first_name, last_name, email, enabled, activated, created_at, deleted_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(business_id.hyphenated())
+        .bind(user.uuid.hyphenated())
+        .bind(&user.first_name)
+        .bind(&user.last_name)
+        .bind(&user.email)
+        .bind(user.enabled)
+        .bind(user.activated)
+        .bind(timestamp(&user.created_at))
+        .bind(user.deleted_at)
+        .execute(&self.pool)
+        .await;
+        match result.map_err(RepositoryError::from) {
+            Ok(_) => Ok(user),
+            // SQLite may report the email index before the primary key; a taken uuid wins.
+            Err(RepositoryError::EmailTaken) if self.get(business_id, user.uuid).await?.is_some() => {
+                Err(RepositoryError::Conflict)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    async fn update(&self, business_id: Uuid, user: User, expected: &User) -> Result<User, RepositoryError> {
+        // Only a row still holding what the caller read is updated; `BINARY` so that a
+        // change to the case of the email counts.
+        let result = sqlx::query(
+            "UPDATE users SET first_name = ?, last_name = ?, email = ?, enabled = ?, activated = ?, deleted_at = ? \
+             WHERE business_id = ? AND uuid = ? AND first_name = ? AND last_name = ? AND email = ? COLLATE BINARY \
+             AND enabled = ? AND activated = ? AND deleted_at IS ?",
+        )
+        .bind(&user.first_name)
+        .bind(&user.last_name)
+        .bind(&user.email)
+        .bind(user.enabled)
+        .bind(user.activated)
+        .bind(user.deleted_at)
+        .bind(business_id.hyphenated())
+        .bind(user.uuid.hyphenated())
+        .bind(&expected.first_name)
+        .bind(&expected.last_name)
+        .bind(&expected.email)
+        .bind(expected.enabled)
+        .bind(expected.activated)
+        .bind(expected.deleted_at)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return match self.get(business_id, user.uuid).await? {
+                Some(_) => Err(RepositoryError::ConcurrentUpdate),
+                None => Err(RepositoryError::NotFound),
+            };
+        }
+        Ok(user)
+    }
+
+    async fn delete(&self, business_id: Uuid, user_id: Uuid) -> Result<(), RepositoryError> {
+        let result = sqlx::query("DELETE FROM users WHERE business_id = ? AND uuid = ?")
+            .bind(business_id.hyphenated())
+            .bind(user_id.hyphenated())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for SqliteStore {
+    async fn find_key(&self, prefix: &str) -> Result<Option<StoredApiKey>, RepositoryError> {
+        let row = sqlx::query("SELECT * FROM api_keys WHERE prefix = ?")
+            .bind(prefix)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(stored_api_key).transpose()?)
+    }
+
+    async fn list_keys(&self, business_id: Uuid) -> Result<Vec<ApiKey>, RepositoryError> {
+        let rows = sqlx::query("SELECT * FROM api_keys WHERE business_id = ? ORDER BY prefix")
+            .bind(business_id.hyphenated())
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(api_key).collect::<Result<_, _>>()?)
+    }
+
+    async fn create_key(&self, key: StoredApiKey) -> Result<(), RepositoryError> {
+        let scopes = serde_json::to_string(&key.api_key.scopes).map_err(|error| RepositoryError::Backend(error.into()))?;
+        sqlx::query(
+            "INSERT INTO api_keys (prefix, business_id, name, scopes, secret_hash, created_at, expires_at, last_used_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&key.api_key.prefix)
+        .bind(key.business_id.hyphenated())
+        .bind(&key.api_key.name)
+        .bind(scopes)
+        .bind(&key.secret_hash)
+        .bind(timestamp(&key.api_key.created_at))
+        .bind(key.api_key.expires_at)
+        .bind(key.api_key.last_used_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn touch_key(&self, prefix: &str, used_at: DateTime<Utc>) -> Result<(), RepositoryError> {
+        let result = sqlx::query("UPDATE api_keys SET last_used_at = ? WHERE prefix = ?")
+            .bind(used_at)
+            .bind(prefix)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn delete_key(&self, business_id: Uuid, prefix: &str) -> Result<(), RepositoryError> {
+        let result = sqlx::query("DELETE FROM api_keys WHERE business_id = ? AND prefix = ?")
+            .bind(business_id.hyphenated())
+            .bind(prefix)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(())
+    }
+}
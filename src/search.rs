@@ -0,0 +1,252 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use uuid::Uuid;
+
+use crate::repository::query::{sort_key, UserSort};
+use crate::repository::{RepositoryError, UserQuery, UserRepository};
+use crate::users::User;
+
+/// How long an index may serve searches before it is rebuilt, which bounds how stale it
+/// can be with respect to writes made by other processes.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// Score for a query that is exactly the user's email.
+const EXACT_EMAIL: u32 = 1000;
+/// Score for a query that is exactly the user's full name.
+const EXACT_NAME: u32 = 100;
+/// Score for each query term that is exactly a name or email token.
+const EXACT_TOKEN: u32 = 3;
+/// Score for each query term that starts a name or email token.
+const TOKEN_PREFIX: u32 = 1;
+
+/// Lowercases and strips diacritics, so that "Zoë" and "ZOE" both fold to "zoe".
+pub fn fold(text: &str) -> String {
+    text.nfkd().filter(|c| !is_combining_mark(*c)).flat_map(char::to_lowercase).collect()
+}
+
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|token| !token.is_empty())
+}
+
+struct Entry {
+    user: User,
+    email: String,
+    name: String,
+}
+
+/// A user matching a search, with its relevance.
+#[derive(Clone, Debug)]
+pub struct Hit {
+    pub score: u32,
+    pub user: User,
+}
+
+/// Inverted index over the live users of one business.
+pub struct BusinessIndex {
+    entries: Vec<Entry>,
+    /// Folded name and email tokens, plus each whole email, to the entries containing them.
+    tokens: BTreeMap<String, Vec<usize>>,
+}
+
+impl BusinessIndex {
+    pub fn build(users: Vec<User>) -> Self {
+        let mut index = Self { entries: Vec::new(), tokens: BTreeMap::new() };
+        for user in users.into_iter().filter(|user| user.deleted_at.is_none()) {
+            let entry = Entry {
+                email: fold(&user.email),
+                name: fold(&format!("{} {}", user.first_name, user.last_name)),
+                user,
+            };
+            let position = index.entries.len();
+            let mut keys: Vec<String> = tokens(&entry.name).chain(tokens(&entry.email)).map(str::to_string).collect();
+            keys.push(entry.email.clone());
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                index.tokens.entry(key).or_default().push(position);
+            }
+            index.entries.push(entry);
+        }
+        index
+    }
+
+    /// Best score each entry earns for one query term. A term such as an email is first
+    /// matched whole; failing that, an entry must match every token of it, so that
+    /// "smith-jones" finds "Smith-Jones".
+    fn term_scores(&self, term: &str) -> HashMap<usize, u32> {
+        let whole = self.token_scores(term);
+        let parts: Vec<&str> = tokens(term).collect();
+        if !whole.is_empty() || parts == [term] {
+            return whole;
+        }
+        let Some((first, rest)) = parts.split_first() else {
+            return whole;
+        };
+        let mut scores = self.token_scores(first);
+        for part in rest {
+            let part_scores = self.token_scores(part);
+            scores.retain(|position, score| match part_scores.get(position) {
+                Some(part_score) => {
+                    *score = (*score).min(*part_score);
+                    true
+                }
+                None => false,
+            });
+        }
+        scores
+    }
+
+    /// Best score each entry earns for one token, by exact or prefix match.
+    fn token_scores(&self, term: &str) -> HashMap<usize, u32> {
+        let mut scores = HashMap::new();
+        for (token, positions) in self.tokens.range(term.to_string()..) {
+            if !token.starts_with(term) {
+                break;
+            }
+            let score = if token == term { EXACT_TOKEN } else { TOKEN_PREFIX };
+            for position in positions {
+                let best = scores.entry(*position).or_insert(0);
+                *best = (*best).max(score);
+            }
+        }
+        scores
+    }
+
+    /// Users matching every term of the query, most relevant first and then in name order.
+    pub fn search(&self, query: &str) -> Vec<Hit> {
+        let query = fold(query.trim());
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        let Some((first, rest)) = terms.split_first() else {
+            return Vec::new();
+        };
+
+        let mut scores = self.term_scores(first);
+        for term in rest {
+            let term_scores = self.term_scores(term);
+            scores.retain(|position, score| match term_scores.get(position) {
+                Some(term_score) => {
+                    *score += term_score;
+                    true
+                }
+                None => false,
+            });
+        }
+
+        let normalized = terms.join(" ");
+        let mut hits: Vec<Hit> = scores
+            .into_iter()
+            .map(|(position, mut score)| {
+                let entry = &self.entries[position];
+                if entry.email == query {
+                    score += EXACT_EMAIL;
+                }
+                if entry.name.split_whitespace().collect::<Vec<_>>().join(" ") == normalized {
+                    score += EXACT_NAME;
+                }
+                Hit { score, user: entry.user.clone() }
+            })
+            .collect();
+        hits.sort_by_cached_key(|hit| (Reverse(hit.score), sort_key(&hit.user, UserSort::Name)));
+        hits
+    }
+}
+
+/// Per-business search indexes, built lazily from the user store.
+pub struct UserSearch {
+    ttl: Duration,
+    businesses: RwLock<HashMap<Uuid, (Instant, Arc<BusinessIndex>)>>,
+    /// Bumped by every invalidation, so that an index built from users listed before a
+    /// write is not kept.
+    generation: AtomicU64,
+}
+
+impl Default for UserSearch {
+    fn default() -> Self {
+        Self::new(DEFAULT_TTL)
+    }
+}
+
+impl UserSearch {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, businesses: RwLock::default(), generation: AtomicU64::new(0) }
+    }
+
+    /// Returns the business's index, rebuilding it if it is missing or expired.
+    pub async fn index(
+        &self,
+        business_id: Uuid,
+        users: &dyn UserRepository,
+    ) -> Result<Arc<BusinessIndex>, RepositoryError> {
+        if let Some((built, index)) = self.businesses.read().unwrap().get(&business_id)
+            && built.elapsed() < self.ttl
+        {
+            return Ok(index.clone());
+        }
+        let generation = self.generation.load(Ordering::Acquire);
+        let index = Arc::new(BusinessIndex::build(users.list(business_id).await?));
+        let mut businesses = self.businesses.write().unwrap();
+        if self.generation.load(Ordering::Acquire) == generation {
+            businesses.insert(business_id, (Instant::now(), index.clone()));
+        }
+        Ok(index)
+    }
+
+    /// Drops the business's index so that the next search sees the latest writes.
+    pub fn invalidate(&self, business_id: Uuid) {
+        let mut businesses = self.businesses.write().unwrap();
+        businesses.remove(&business_id);
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Wraps a user store so that writes through it invalidate the search index.
+pub struct Indexed {
+    pub users: Arc<dyn UserRepository>,
+    pub search: Arc<UserSearch>,
+}
+
+impl Indexed {
+    fn written<T>(&self, business_id: Uuid, result: Result<T, RepositoryError>) -> Result<T, RepositoryError> {
+        if result.is_ok() {
+            self.search.invalidate(business_id);
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl UserRepository for Indexed {
+    async fn get(&self, business_id: Uuid, user_id: Uuid) -> Result<Option<User>, RepositoryError> {
+        self.users.get(business_id, user_id).await
+    }
+
+    async fn list(&self, business_id: Uuid) -> Result<Vec<User>, RepositoryError> {
+        self.users.list(business_id).await
+    }
+
+    async fn query(&self, business_id: Uuid, query: &UserQuery) -> Result<Vec<User>, RepositoryError> {
+        self.users.query(business_id, query).await
+    }
+
+    async fn create(&self, business_id: Uuid, user: User) -> Result<User, RepositoryError> {
+        let result = self.users.create(business_id, user).await;
+        self.written(business_id, result)
+    }
+
+    async fn update(&self, business_id: Uuid, user: User, expected: &User) -> Result<User, RepositoryError> {
+        let result = self.users.update(business_id, user, expected).await;
+        self.written(business_id, result)
+    }
+
+    async fn delete(&self, business_id: Uuid, user_id: Uuid) -> Result<(), RepositoryError> {
+        let result = self.users.delete(business_id, user_id).await;
+        self.written(business_id, result)
+    }
+}
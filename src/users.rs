@@ -0,0 +1,653 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::State, response::IntoResponse, http::{header, StatusCode}, Json};
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Map, Value};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::businesses;
+use crate::error::{ApiError, ProblemDetails};
+use crate::extract::{self, BusinessPath, JsonObject, Query, UserPath};
+use crate::pagination::{decode_cursor, encode_cursor, Page, DEFAULT_LIMIT, MAX_LIMIT};
+use crate::patch::{merge, MergePatch};
+use crate::repository::query::{sort_key, SortOrder, UserSort};
+use crate::repository::{RepositoryError, UserQuery};
+use crate::validation;
+use crate::AppState;
+
+/// User Account
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Represents a user account within the business.
+pub struct User {
+    #[schema(
+        example = "550e8400-e29b-41d4-a716-446655440000",
+    )]
+    /// Unique identifier for the user.
+    pub uuid: Uuid,
+    #[schema(
+        example = "Jane",
+    )]
+    /// First name of the user.
+    pub first_name: String,
+    #[schema(
+        example = "Doe",
+    )]
+    /// Last name of the user.
+    pub last_name: String,
+    #[schema(
+        example = "jane.doe@example.com",
+    )]
+    /// Email address of the user.
+    pub email: String,
+    #[schema(
+        example = true,
+    )]
+    /// Whether the user's account is enabled.
+    pub enabled: bool,
+    #[schema(
+        example = true,
+    )]
+    /// Whether the user's account is activated.
+    pub activated: bool,
+    #[schema(
+        read_only,
+        example = "2025-01-01T09:30:00Z",
+    )]
+    /// When the user was created.
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(
+        read_only,
+    )]
+    /// When the user was soft-deleted; deleted users are hidden until restored.
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// New User Account
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Details of a user account to create; the server assigns its uuid.
+pub struct CreateUser {
+    #[schema(
+        example = "Jane",
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// First name of the user.
+    pub first_name: String,
+    #[schema(
+        example = "Doe",
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// Last name of the user.
+    pub last_name: String,
+    #[schema(
+        example = "jane.doe@example.com",
+        format = Email,
+        max_length = 254,
+    )]
+    /// Email address of the user, unique within the business.
+    pub email: String,
+    #[serde(default = "enabled_by_default")]
+    #[schema(
+        example = true,
+        default = true,
+    )]
+    /// Whether the user's account is enabled. Defaults to `true`.
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Editable User Account
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// Every field of a user account a client may change; the uuid is immutable.
+pub struct UpdateUser {
+    #[schema(
+        example = "Jane",
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// First name of the user.
+    pub first_name: String,
+    #[schema(
+        example = "Doe",
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// Last name of the user.
+    pub last_name: String,
+    #[schema(
+        example = "jane.doe@example.com",
+        format = Email,
+        max_length = 254,
+    )]
+    /// Email address of the user, unique within the business.
+    pub email: String,
+    #[schema(
+        example = true,
+    )]
+    /// Whether the user's account is enabled.
+    pub enabled: bool,
+    #[schema(
+        example = true,
+    )]
+    /// Whether the user's account is activated.
+    pub activated: bool,
+}
+
+impl UpdateUser {
+    /// Applies the edits to `current`, keeping the fields clients cannot change.
+    fn apply(self, current: User) -> User {
+        User {
+            first_name: self.first_name,
+            last_name: self.last_name,
+            email: self.email,
+            enabled: self.enabled,
+            activated: self.activated,
+            ..current
+        }
+    }
+}
+
+impl From<User> for UpdateUser {
+    fn from(user: User) -> Self {
+        Self {
+            first_name: user.first_name,
+            last_name: user.last_name,
+            email: user.email,
+            enabled: user.enabled,
+            activated: user.activated,
+        }
+    }
+}
+
+/// User Account Patch
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+/// JSON Merge Patch (RFC 7396) of a user account; omitted fields are left unchanged.
+pub struct UserPatch {
+    #[schema(
+        example = "Janet",
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// First name of the user.
+    pub first_name: Option<String>,
+    #[schema(
+        min_length = 1,
+        max_length = 100,
+        pattern = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$",
+    )]
+    /// Last name of the user.
+    pub last_name: Option<String>,
+    #[schema(
+        format = Email,
+        max_length = 254,
+    )]
+    /// Email address of the user, unique within the business.
+    pub email: Option<String>,
+    #[schema(
+        example = false,
+    )]
+    /// Whether the user's account is enabled.
+    pub enabled: Option<bool>,
+    /// Whether the user's account is activated.
+    pub activated: Option<bool>,
+}
+
+/// Checks the edited document against the `UpdateUser` schema, together with any
+/// problems already found, so that every bad field is reported at once.
+fn editable(document: Map<String, Value>, mut errors: BTreeMap<String, String>) -> Result<UpdateUser, ApiError> {
+    errors.extend(validation::check::<UpdateUser>(&document));
+    if !errors.is_empty() {
+        return Err(ApiError::Validation(errors));
+    }
+    serde_json::from_value(Value::Object(document)).map_err(|error| ApiError::Unprocessable(error.to_string()))
+}
+
+/// Looks up a user that has not been soft-deleted, in a business that exists.
+async fn live_user(state: &AppState, business_id: Uuid, user_id: Uuid) -> Result<User, ApiError> {
+    businesses::existing(state, business_id).await?;
+    live(state, business_id, user_id).await
+}
+
+/// Looks up a user that has not been soft-deleted, in a business that is not archived.
+async fn editable_user(state: &AppState, business_id: Uuid, user_id: Uuid) -> Result<User, ApiError> {
+    businesses::active(state, business_id).await?;
+    live(state, business_id, user_id).await
+}
+
+/// Looks up a user that has not been soft-deleted.
+async fn live(state: &AppState, business_id: Uuid, user_id: Uuid) -> Result<User, ApiError> {
+    match state.users.get(business_id, user_id).await? {
+        Some(user) if user.deleted_at.is_some() => Err(ApiError::UserDeleted),
+        Some(user) => Ok(user),
+        None => Err(ApiError::UserNotFound),
+    }
+}
+
+/// Maps the errors a user store call can raise that depend on the resource.
+fn user_error(error: RepositoryError) -> ApiError {
+    match error {
+        RepositoryError::NotFound => ApiError::UserNotFound,
+        RepositoryError::Conflict => ApiError::UserExists,
+        error => error.into(),
+    }
+}
+
+/// Checks a requested page size.
+fn page_limit(limit: Option<usize>) -> Result<usize, ApiError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(ApiError::BadRequest(format!("limit must be between 1 and {MAX_LIMIT}")));
+    }
+    Ok(limit)
+}
+
+/// Get user account by user id
+#[utoipa::path(
+    get,
+    path = "/business/{businessId}/users/{userId}",
+    responses(
+        (status = 200, description = "User", body = User),
+        (status = 404, description = "User or business not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 410, description = "User has been deleted", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id of the user"),
+        ("userId" = Uuid, Path, description = "User id to get user"),
+    ),
+    security(("bearerAuth" = ["users:read"]), ("apiKey" = ["users:read"]))
+)]
+pub async fn get_user_by_id(
+    State(state): State<AppState>,
+    UserPath(business_id, user_id): UserPath,
+) -> Result<Json<User>, ApiError> {
+    Ok(Json(live_user(&state, business_id, user_id).await?))
+}
+
+/// Create user account
+#[utoipa::path(
+    post,
+    path = "/business/{businessId}/users",
+    request_body = CreateUser,
+    responses(
+        (status = 201, description = "User created", body = User,
+            headers(("Location" = String, description = "Path of the created user"))),
+        (status = 404, description = "Business not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Email already in use within the business, or the business is archived", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 415, description = "Body is not `application/json`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Fields are missing, unknown or have the wrong type", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id of the user"),
+    ),
+    security(("bearerAuth" = ["users:write"]), ("apiKey" = ["users:write"]))
+)]
+pub async fn create_user(
+    State(state): State<AppState>,
+    BusinessPath(business_id): BusinessPath,
+    extract::Json(new_user): extract::Json<CreateUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    businesses::active(&state, business_id).await?;
+    let user = User {
+        uuid: Uuid::new_v4(),
+        first_name: new_user.first_name,
+        last_name: new_user.last_name,
+        email: new_user.email,
+        enabled: new_user.enabled,
+        activated: false,
+        // Stores keep microseconds, so truncate up front for the response to match later reads.
+        created_at: Utc::now().trunc_subsecs(6),
+        deleted_at: None,
+    };
+    let user = state.users.create(business_id, user).await.map_err(user_error)?;
+    let location = format!("/business/{business_id}/users/{}", user.uuid);
+    Ok((StatusCode::CREATED, [(header::LOCATION, location)], Json(user)))
+}
+
+/// Replace user account
+#[utoipa::path(
+    put,
+    path = "/business/{businessId}/users/{userId}",
+    request_body = UpdateUser,
+    responses(
+        (status = 200, description = "User", body = User),
+        (status = 404, description = "User or business not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Email already in use within the business, the user was changed by another request, or the business is archived", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 410, description = "User has been deleted", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 415, description = "Body is not `application/json`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Fields are missing, unknown, have the wrong type or change the uuid", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id of the user"),
+        ("userId" = Uuid, Path, description = "User id to replace"),
+    ),
+    security(("bearerAuth" = ["users:write"]), ("apiKey" = ["users:write"]))
+)]
+pub async fn replace_user(
+    State(state): State<AppState>,
+    UserPath(business_id, user_id): UserPath,
+    JsonObject(mut document): JsonObject,
+) -> Result<Json<User>, ApiError> {
+    let current = editable_user(&state, business_id, user_id).await?;
+    let mut errors = BTreeMap::new();
+    validation::take_uuid(&mut document, user_id, &mut errors);
+    let update = editable(document, errors)?;
+    Ok(Json(state.users.update(business_id, update.apply(current.clone()), &current).await.map_err(user_error)?))
+}
+
+/// Update user account fields
+#[utoipa::path(
+    patch,
+    path = "/business/{businessId}/users/{userId}",
+    request_body(content = UserPatch, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "User", body = User),
+        (status = 404, description = "User or business not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Email already in use within the business, the user was changed by another request, or the business is archived", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 410, description = "User has been deleted", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 415, description = "Body is not `application/merge-patch+json`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Fields are unknown, have the wrong type, are removed or change the uuid", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id of the user"),
+        ("userId" = Uuid, Path, description = "User id to update"),
+    ),
+    security(("bearerAuth" = ["users:write"]), ("apiKey" = ["users:write"]))
+)]
+pub async fn patch_user(
+    State(state): State<AppState>,
+    UserPath(business_id, user_id): UserPath,
+    MergePatch(mut patch): MergePatch,
+) -> Result<Json<User>, ApiError> {
+    let current = editable_user(&state, business_id, user_id).await?;
+
+    let mut errors = BTreeMap::new();
+    validation::take_uuid(&mut patch, user_id, &mut errors);
+    let mut document = json!(UpdateUser::from(current.clone()));
+    merge(&mut document, &Value::Object(patch));
+    let Value::Object(document) = document else {
+        unreachable!("merging an object patch yields an object");
+    };
+    let update = editable(document, errors)?;
+    Ok(Json(state.users.update(business_id, update.apply(current.clone()), &current).await.map_err(user_error)?))
+}
+
+/// Options for deleting a user account.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteUserQuery {
+    #[serde(default)]
+    /// Remove the user permanently instead of soft-deleting it.
+    pub hard: bool,
+}
+
+/// Delete user account
+///
+/// Soft-deletes the user by default so that it can be restored; `hard=true` removes it permanently.
+#[utoipa::path(
+    delete,
+    path = "/business/{businessId}/users/{userId}",
+    responses(
+        (status = 204, description = "User deleted"),
+        (status = 404, description = "User or business not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "User was changed by another request, or the business is archived", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id of the user"),
+        ("userId" = Uuid, Path, description = "User id to delete"),
+        DeleteUserQuery,
+    ),
+    security(("bearerAuth" = ["users:write"]), ("apiKey" = ["users:write"]))
+)]
+pub async fn delete_user(
+    State(state): State<AppState>,
+    UserPath(business_id, user_id): UserPath,
+    Query(query): Query<DeleteUserQuery>,
+) -> Result<StatusCode, ApiError> {
+    businesses::active(&state, business_id).await?;
+    if query.hard {
+        state.users.delete(business_id, user_id).await.map_err(user_error)?;
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    let current = state.users.get(business_id, user_id).await?.ok_or(ApiError::UserNotFound)?;
+    if current.deleted_at.is_none() {
+        let user = User { deleted_at: Some(Utc::now()), ..current.clone() };
+        state.users.update(business_id, user, &current).await.map_err(user_error)?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Restore soft-deleted user account
+#[utoipa::path(
+    post,
+    path = "/business/{businessId}/users/{userId}/restore",
+    responses(
+        (status = 200, description = "User", body = User),
+        (status = 404, description = "User or business not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "User was changed by another request, or the business is archived", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id of the user"),
+        ("userId" = Uuid, Path, description = "User id to restore"),
+    ),
+    security(("bearerAuth" = ["users:write"]), ("apiKey" = ["users:write"]))
+)]
+pub async fn restore_user(
+    State(state): State<AppState>,
+    UserPath(business_id, user_id): UserPath,
+) -> Result<Json<User>, ApiError> {
+    businesses::active(&state, business_id).await?;
+    let current = state.users.get(business_id, user_id).await?.ok_or(ApiError::UserNotFound)?;
+    if current.deleted_at.is_none() {
+        return Ok(Json(current));
+    }
+    let user = User { deleted_at: None, ..current.clone() };
+    Ok(Json(state.users.update(business_id, user, &current).await.map_err(user_error)?))
+}
+
+/// Filters, ordering and paging for listing user accounts.
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersQuery {
+    /// Only users whose account is (or is not) enabled.
+    pub enabled: Option<bool>,
+    /// Only users whose account is (or is not) activated.
+    pub activated: Option<bool>,
+    #[param(
+        example = "jane",
+    )]
+    /// Only users whose email starts with this, ignoring case.
+    pub email_prefix: Option<String>,
+    #[param(
+        inline,
+    )]
+    /// Field to sort by. Defaults to `name`.
+    pub sort: Option<UserSort>,
+    #[param(
+        inline,
+    )]
+    /// Sort direction. Defaults to `asc`.
+    pub order: Option<SortOrder>,
+    #[param(
+        minimum = 1,
+        maximum = 100,
+    )]
+    /// Maximum number of users to return. Defaults to 20.
+    pub limit: Option<usize>,
+    /// `nextCursor` from the previous page.
+    pub cursor: Option<String>,
+}
+
+/// Position of a page within a listing; only valid for the same sort and order.
+#[derive(Serialize, Deserialize)]
+struct ListCursor {
+    sort: UserSort,
+    order: SortOrder,
+    after: Vec<String>,
+}
+
+/// List user accounts
+///
+/// Soft-deleted users are not listed.
+#[utoipa::path(
+    get,
+    path = "/business/{businessId}/users",
+    responses(
+        (status = 200, description = "Page of users", body = Page<User>),
+        (status = 400, description = "Malformed path parameter, limit or cursor", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Business not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id of the users"),
+        ListUsersQuery,
+    ),
+    security(("bearerAuth" = ["users:read"]), ("apiKey" = ["users:read"]))
+)]
+pub async fn list_users(
+    State(state): State<AppState>,
+    BusinessPath(business_id): BusinessPath,
+    Query(params): Query<ListUsersQuery>,
+) -> Result<Json<Page<User>>, ApiError> {
+    let limit = page_limit(params.limit)?;
+    businesses::existing(&state, business_id).await?;
+    let sort = params.sort.unwrap_or_default();
+    let order = params.order.unwrap_or_default();
+    let after = match params.cursor.as_deref().map(decode_cursor::<ListCursor>) {
+        None => None,
+        Some(Some(cursor))
+            if cursor.sort == sort && cursor.order == order && cursor.after.len() == sort.key_len() =>
+        {
+            Some(cursor.after)
+        }
+        Some(_) => return Err(ApiError::BadRequest("cursor is invalid for this sort and order".to_string())),
+    };
+    let query = UserQuery {
+        enabled: params.enabled,
+        activated: params.activated,
+        email_prefix: params.email_prefix,
+        sort,
+        order,
+        after,
+        limit,
+    };
+
+    let mut users = state.users.query(business_id, &query).await?;
+    let next_cursor = if users.len() > limit {
+        users.truncate(limit);
+        users.last().map(|last| encode_cursor(&ListCursor { sort, order, after: sort_key(last, sort) }))
+    } else {
+        None
+    };
+    Ok(Json(Page { items: users, next_cursor }))
+}
+
+/// Search terms, filters and paging for searching user accounts.
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct SearchUsersQuery {
+    #[param(
+        example = "jane doe",
+    )]
+    /// Words to find in first name, last name or email, ignoring case and diacritics.
+    /// Every word must match the start of a name or email part.
+    pub q: String,
+    /// Only users whose account is (or is not) enabled.
+    pub enabled: Option<bool>,
+    /// Only users whose account is (or is not) activated.
+    pub activated: Option<bool>,
+    #[param(
+        minimum = 1,
+        maximum = 100,
+    )]
+    /// Maximum number of users to return. Defaults to 20.
+    pub limit: Option<usize>,
+    /// `nextCursor` from the previous page.
+    pub cursor: Option<String>,
+}
+
+/// Position of a page within search results; only valid for the same search.
+#[derive(Serialize, Deserialize)]
+struct SearchCursor {
+    q: String,
+    score: u32,
+    after: Vec<String>,
+}
+
+/// Search user accounts
+///
+/// Results are ranked: an exact email match first, then exact name matches, then by how
+/// many words match whole name or email parts. Soft-deleted users are not returned.
+#[utoipa::path(
+    get,
+    path = "/business/{businessId}/users/search",
+    responses(
+        (status = 200, description = "Page of matching users", body = Page<User>),
+        (status = 400, description = "Malformed path parameter, limit or cursor", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Business not found", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+    params(
+        ("businessId" = Uuid, Path, description = "Business id of the users"),
+        SearchUsersQuery,
+    ),
+    security(("bearerAuth" = ["users:read"]), ("apiKey" = ["users:read"]))
+)]
+pub async fn search_users(
+    State(state): State<AppState>,
+    BusinessPath(business_id): BusinessPath,
+    Query(params): Query<SearchUsersQuery>,
+) -> Result<Json<Page<User>>, ApiError> {
+    let limit = page_limit(params.limit)?;
+    businesses::existing(&state, business_id).await?;
+    let after = match params.cursor.as_deref().map(decode_cursor::<SearchCursor>) {
+        None => None,
+        Some(Some(cursor)) if cursor.q == params.q => Some((cursor.score, cursor.after)),
+        Some(_) => return Err(ApiError::BadRequest("cursor is invalid for this search".to_string())),
+    };
+
+    let index = state.search.index(business_id, state.users.as_ref()).await?;
+    let mut hits: Vec<_> = index
+        .search(&params.q)
+        .into_iter()
+        .filter(|hit| params.enabled.is_none_or(|enabled| hit.user.enabled == enabled))
+        .filter(|hit| params.activated.is_none_or(|activated| hit.user.activated == activated))
+        .filter(|hit| match &after {
+            None => true,
+            Some((score, key)) => {
+                hit.score < *score || (hit.score == *score && sort_key(&hit.user, UserSort::Name) > *key)
+            }
+        })
+        .take(limit + 1)
+        .collect();
+
+    let next_cursor = if hits.len() > limit {
+        hits.truncate(limit);
+        hits.last().map(|last| {
+            encode_cursor(&SearchCursor {
+                q: params.q.clone(),
+                score: last.score,
+                after: sort_key(&last.user, UserSort::Name),
+            })
+        })
+    } else {
+        None
+    };
+    let items = hits.into_iter().map(|hit| hit.user).collect();
+    Ok(Json(Page { items, next_cursor }))
+}
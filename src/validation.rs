@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{LazyLock, Mutex};
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use utoipa::openapi::schema::{Array, ArrayItems, KnownFormat, Object, Schema, SchemaFormat, SchemaType, Type};
+use utoipa::openapi::RefOr;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+fn matches_type(value: &Value, schema_type: &Type) -> bool {
+    match schema_type {
+        Type::Object => value.is_object(),
+        Type::String => value.is_string(),
+        Type::Integer => value.is_i64() || value.is_u64(),
+        Type::Number => value.is_number(),
+        Type::Boolean => value.is_boolean(),
+        Type::Array => value.is_array(),
+        Type::Null => value.is_null(),
+    }
+}
+
+fn type_name(schema_type: &Type) -> &'static str {
+    match schema_type {
+        Type::Object => "an object",
+        Type::String => "a string",
+        Type::Integer => "an integer",
+        Type::Number => "a number",
+        Type::Boolean => "a boolean",
+        Type::Array => "an array",
+        Type::Null => "null",
+    }
+}
+
+/// Pattern for free text such as names: no leading or trailing whitespace and no control
+/// characters. Written to be valid as both an ECMA-262 and a `regex` pattern.
+pub const TRIMMED_TEXT: &str = r"^[^\s\x00-\x1f\x7f-\x9f](?:[^\x00-\x1f\x7f-\x9f]*[^\s\x00-\x1f\x7f-\x9f])?$";
+
+/// Pattern for slugs: lowercase letters and digits in groups joined by single hyphens.
+pub const SLUG: &str = r"^[a-z0-9]+(?:-[a-z0-9]+)*$";
+
+/// Pattern for IANA time zone names such as `Europe/London`. Whether the zone exists is not
+/// checked, as the database changes.
+pub const TIME_ZONE: &str = r"^[A-Za-z]+(?:[/_+-][A-Za-z0-9]+)*$";
+
+/// Pattern for BCP 47 language tags such as `en-GB`, loosely.
+pub const LANGUAGE_TAG: &str = r"^[a-z]{2,3}(?:-[A-Za-z0-9]{2,8})*$";
+
+/// Readable explanations of the patterns schemas use, shown instead of the pattern itself.
+const PATTERN_MESSAGES: &[(&str, &str)] = &[
+    (TRIMMED_TEXT, "must not start or end with whitespace or contain control characters"),
+    (SLUG, "must be lowercase letters and digits joined by single hyphens, such as `acme-ltd`"),
+    (TIME_ZONE, "must be an IANA time zone name such as `Europe/London`"),
+    (LANGUAGE_TAG, "must be a language tag such as `en-GB`"),
+];
+
+/// Compiled schema patterns, which are few and fixed.
+static PATTERNS: LazyLock<Mutex<HashMap<String, Regex>>> = LazyLock::new(Default::default);
+
+fn matches_pattern(value: &str, pattern: &str) -> bool {
+    let mut patterns = PATTERNS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !patterns.contains_key(pattern) {
+        match Regex::new(pattern) {
+            Ok(regex) => patterns.insert(pattern.to_string(), regex),
+            // A schema pattern the regex engine cannot read is not the client's fault.
+            Err(_) => return true,
+        };
+    }
+    patterns[pattern].is_match(value)
+}
+
+/// A deliberately loose check of an email address: something before and after a single `@`,
+/// a dotted domain and no whitespace. Whether the mailbox exists is for a confirmation email.
+fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.split('.').count() > 1
+        && domain.split('.').all(|label| !label.is_empty())
+        && !value.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// What is wrong with a string documented by `object`, if anything.
+fn check_string(value: &str, object: &Object) -> Option<String> {
+    let length = value.chars().count();
+    if let Some(min_length) = object.min_length
+        && length < min_length
+    {
+        return Some(match min_length {
+            1 => "must not be empty".to_string(),
+            _ => format!("must be at least {min_length} characters"),
+        });
+    }
+    if let Some(max_length) = object.max_length
+        && length > max_length
+    {
+        return Some(format!("must be at most {max_length} characters"));
+    }
+    if let Some(pattern) = &object.pattern
+        && !matches_pattern(value, pattern)
+    {
+        let message = PATTERN_MESSAGES.iter().find(|(known, _)| known == pattern);
+        return Some(match message {
+            Some((_, message)) => message.to_string(),
+            None => format!("must match the pattern `{pattern}`"),
+        });
+    }
+    match object.format {
+        Some(SchemaFormat::KnownFormat(KnownFormat::Email)) if !is_email(value) => {
+            Some("must be an email address".to_string())
+        }
+        Some(SchemaFormat::KnownFormat(KnownFormat::DateTime)) if chrono::DateTime::parse_from_rfc3339(value).is_err() => {
+            Some("must be an RFC 3339 date-time such as 2030-01-01T00:00:00Z".to_string())
+        }
+        _ => None,
+    }
+}
+
+/// What is wrong with the items of an array documented by `array`, if anything.
+fn check_items(items: &[Value], array: &Array) -> Option<String> {
+    if let Some(min_items) = array.min_items
+        && items.len() < min_items
+    {
+        return Some(match min_items {
+            1 => "must not be empty".to_string(),
+            _ => format!("must have at least {min_items} items"),
+        });
+    }
+    if let Some(max_items) = array.max_items
+        && items.len() > max_items
+    {
+        return Some(format!("must have at most {max_items} items"));
+    }
+    let ArrayItems::RefOrSchema(schema) = &array.items else {
+        return None;
+    };
+    items.iter().enumerate().find_map(|(index, item)| {
+        check_value(item, schema).map(|problem| format!("item {} {problem}", index + 1))
+    })
+}
+
+/// The schema itself, out of the one-item `oneOf` utoipa wraps an inlined schema in when
+/// the field describes it.
+fn unwrapped(schema: &RefOr<Schema>) -> &RefOr<Schema> {
+    match schema {
+        RefOr::T(Schema::OneOf(one_of)) if one_of.items.len() == 1 => &one_of.items[0],
+        schema => schema,
+    }
+}
+
+/// What is wrong with a value documented by `schema`, if anything.
+fn check_value(value: &Value, schema: &RefOr<Schema>) -> Option<String> {
+    let object = match unwrapped(schema) {
+        RefOr::T(Schema::Object(object)) => object,
+        RefOr::T(Schema::Array(array)) => {
+            return match value.as_array() {
+                Some(items) => check_items(items, array),
+                None if value.is_null() && matches!(&array.schema_type, SchemaType::Array(types) if types.contains(&Type::Null)) => None,
+                None => Some("must be an array".to_string()),
+            };
+        }
+        _ => return None,
+    };
+    if let Some(allowed) = &object.enum_values
+        && !allowed.contains(value)
+    {
+        let allowed: Vec<_> = allowed.iter().map(|value| format!("`{}`", value.as_str().unwrap_or_default())).collect();
+        return Some(format!("must be one of {}", allowed.join(", ")));
+    }
+    let types = match &object.schema_type {
+        SchemaType::Type(schema_type) => std::slice::from_ref(schema_type),
+        SchemaType::Array(types) => types.as_slice(),
+        SchemaType::AnyValue => return None,
+    };
+    if types.iter().any(|schema_type| matches_type(value, schema_type)) {
+        return value.as_str().and_then(|value| check_string(value, object));
+    }
+    let names: Vec<_> = types.iter().map(type_name).collect();
+    Some(format!("must be {}", names.join(" or ")))
+}
+
+/// Adds the problems with the fields of `document` to `errors`, keyed by their name after
+/// `prefix`. Fields that are objects with documented properties are checked field by field.
+fn check_fields(document: &Map<String, Value>, schema: &Object, prefix: &str, errors: &mut BTreeMap<String, String>) {
+    for (name, value) in document {
+        let key = format!("{prefix}{name}");
+        let problem = match (schema.properties.get(name).map(unwrapped), value) {
+            (Some(RefOr::T(Schema::Object(nested))), Value::Object(fields)) if !nested.properties.is_empty() => {
+                check_fields(fields, nested, &format!("{key}."), errors);
+                None
+            }
+            (Some(property), _) => check_value(value, property),
+            (None, _) => Some("is not a known field".to_string()),
+        };
+        if let Some(problem) = problem {
+            errors.insert(key, problem);
+        }
+    }
+    for name in &schema.required {
+        if !document.contains_key(name) {
+            errors.insert(format!("{prefix}{name}"), "is required".to_string());
+        }
+    }
+}
+
+/// Checks a JSON object against the schema `T` documents: every required field present,
+/// no unknown fields, every value of the documented type, every string within the
+/// documented length, pattern, format and enum, and every array within its documented
+/// length with items that pass the same checks. Problems are keyed by field, and nested
+/// fields by their dotted path such as `settings.locale`, so that a client learns about all
+/// of them at once.
+pub fn check<T: ToSchema>(document: &Map<String, Value>) -> BTreeMap<String, String> {
+    let mut errors = BTreeMap::new();
+    if let RefOr::T(Schema::Object(schema)) = T::schema() {
+        check_fields(document, &schema, "", &mut errors);
+    }
+    errors
+}
+
+/// Removes `uuid` from a request body, which may only repeat the resource's existing uuid.
+pub fn take_uuid(document: &mut Map<String, Value>, id: Uuid, errors: &mut BTreeMap<String, String>) {
+    if let Some(uuid) = document.remove("uuid")
+        && uuid.as_str().and_then(|uuid| uuid.parse().ok()) != Some(id)
+    {
+        errors.insert("uuid".to_string(), "is immutable".to_string());
+    }
+}
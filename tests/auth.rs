@@ -0,0 +1,414 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use axum::body::{to_bytes, Body};
+use axum::extract::State;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use rust_lambda_api_poc::api_keys::{ApiKey, StoredApiKey};
+use rust_lambda_api_poc::auth::{authenticate, AuthConfig, Authenticator, Claims, JwksSource};
+use rust_lambda_api_poc::repository::memory::InMemoryStore;
+use rust_lambda_api_poc::repository::{ApiKeyRepository, RepositoryError};
+use rust_lambda_api_poc::{app, AppState};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const ISSUER: &str = "https://auth.example.com/";
+const AUDIENCE: &str = "users-api";
+const BUSINESS: &str = "7c9e6679-7425-40de-944b-e07fc1f90ae7";
+const OTHER_BUSINESS: &str = "3f2504e0-4f89-11d3-9a0c-0305e82c3301";
+const USERS: &str = "/business/7c9e6679-7425-40de-944b-e07fc1f90ae7/users";
+const JANE: &str = "/business/7c9e6679-7425-40de-944b-e07fc1f90ae7/users/550e8400-e29b-41d4-a716-446655440000";
+
+fn rsa_key() -> EncodingKey {
+    EncodingKey::from_rsa_pem(include_bytes!("fixtures/jwt/rsa.pem")).unwrap()
+}
+
+fn ec_key() -> EncodingKey {
+    EncodingKey::from_ec_pem(include_bytes!("fixtures/jwt/ec.pem")).unwrap()
+}
+
+/// The public halves of the fixture keys, as an identity provider would publish them.
+fn jwks() -> JwkSet {
+    let jwk = |key: &EncodingKey, algorithm, kid: &str| {
+        let mut jwk = Jwk::from_encoding_key(key, algorithm).unwrap();
+        jwk.common.key_id = Some(kid.to_string());
+        jwk
+    };
+    JwkSet { keys: vec![jwk(&rsa_key(), Algorithm::RS256, "rsa"), jwk(&ec_key(), Algorithm::ES256, "ec")] }
+}
+
+fn jwks_file() -> PathBuf {
+    let path = std::env::temp_dir().join(format!("jwks-{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(&path, serde_json::to_string(&jwks()).unwrap()).unwrap();
+    path
+}
+
+fn config(jwks: JwksSource) -> AuthConfig {
+    AuthConfig { issuer: ISSUER.to_string(), audience: AUDIENCE.to_string(), jwks, jwks_ttl: 300 }
+}
+
+fn router() -> Router {
+    let users = InMemoryStore::from_json(include_str!("fixtures/users.json")).unwrap();
+    app(AppState::new(users).with_auth(Authenticator::new(&config(JwksSource::File(jwks_file())))))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+fn claims() -> Value {
+    json!({
+        "sub": "user-1",
+        "iss": ISSUER,
+        "aud": AUDIENCE,
+        "exp": now() + 600,
+        "scope": "users:read users:write api-keys:read api-keys:write",
+        "businesses": [BUSINESS],
+    })
+}
+
+fn token(algorithm: Algorithm, kid: &str, claims: &Value) -> String {
+    let key = if algorithm == Algorithm::ES256 { ec_key() } else { rsa_key() };
+    let header = Header { kid: Some(kid.to_string()), ..Header::new(algorithm) };
+    jsonwebtoken::encode(&header, claims, &key).unwrap()
+}
+
+async fn send(router: &Router, method: Method, uri: &str, token: Option<&str>) -> (StatusCode, axum::http::HeaderMap, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    respond(router, request.body(Body::empty()).unwrap()).await
+}
+
+async fn respond(router: &Router, request: Request<Body>) -> (StatusCode, axum::http::HeaderMap, Value) {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let (parts, body) = response.into_parts();
+    let body = serde_json::from_slice(&to_bytes(body, usize::MAX).await.unwrap()).unwrap_or(Value::Null);
+    (parts.status, parts.headers, body)
+}
+
+#[tokio::test]
+async fn rs256_and_es256_tokens_are_accepted() {
+    let router = router();
+    for (algorithm, kid) in [(Algorithm::RS256, "rsa"), (Algorithm::ES256, "ec")] {
+        let (status, _, _) = send(&router, Method::GET, USERS, Some(&token(algorithm, kid, &claims()))).await;
+        assert_eq!(status, StatusCode::OK, "{algorithm:?}");
+    }
+}
+
+#[tokio::test]
+async fn missing_token_is_a_401_with_a_challenge() {
+    let (status, headers, problem) = send(&router(), Method::GET, USERS, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(headers[header::WWW_AUTHENTICATE], r#"Bearer realm="api""#);
+    assert_eq!(problem["type"], "/problems/missing-credentials");
+    assert!(problem["correlationId"].is_string());
+}
+
+#[tokio::test]
+async fn requests_are_refused_unless_anonymous_access_is_on() {
+    let users = || InMemoryStore::from_json(include_str!("fixtures/users.json")).unwrap();
+    let token = token(Algorithm::RS256, "rsa", &claims());
+
+    let unconfigured = app(AppState::new(users()));
+    let (status, _, problem) = send(&unconfigured, Method::GET, USERS, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(problem["type"], "/problems/missing-credentials");
+    let (status, _, problem) = send(&unconfigured, Method::GET, USERS, Some(&token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(problem["type"], "/problems/invalid-token");
+
+    let anonymous = app(AppState::new(users()).with_anonymous_access());
+    let (status, _, _) = send(&anonymous, Method::GET, USERS, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, problem) = send(&anonymous, Method::GET, USERS, Some(&token)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "a token is rejected rather than ignored");
+    assert_eq!(problem["type"], "/problems/invalid-token");
+}
+
+#[tokio::test]
+async fn invalid_tokens_are_rejected_with_a_reason() {
+    let router = router();
+    let with = |key: &str, value: Value| {
+        let mut claims = claims();
+        claims[key] = value;
+        claims
+    };
+    let hs256 = jsonwebtoken::encode(
+        &Header::new(Algorithm::HS256),
+        &claims(),
+        &EncodingKey::from_secret(b"anyone can sign this"),
+    )
+    .unwrap();
+    let cases = [
+        (token(Algorithm::RS256, "rsa", &with("exp", json!(now() - 600))), "it has expired"),
+        (token(Algorithm::RS256, "rsa", &with("aud", json!("another-api"))), "it is not meant for this API"),
+        (token(Algorithm::RS256, "rsa", &with("iss", json!("https://evil.example.com/"))), "it was not issued by the expected issuer"),
+        (token(Algorithm::RS256, "unknown", &claims()), "it is signed with an unknown key"),
+        (token(Algorithm::ES256, "rsa", &claims()), "it is signed with an unknown key"),
+        (hs256, "HS256 signatures are not accepted"),
+        ("not.a.token".to_string(), "it is malformed"),
+    ];
+    for (token, reason) in cases {
+        let (status, headers, problem) = send(&router, Method::GET, USERS, Some(&token)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{reason}");
+        assert_eq!(headers[header::WWW_AUTHENTICATE], r#"Bearer realm="api", error="invalid_token""#);
+        assert_eq!(problem["type"], "/problems/invalid-token");
+        assert_eq!(problem["detail"], format!("The bearer token is not valid: {reason}."));
+    }
+}
+
+#[tokio::test]
+async fn scopes_are_checked_per_operation() {
+    let router = router();
+    let mut read_only = claims();
+    read_only["scope"] = json!("users:read");
+    let token = token(Algorithm::RS256, "rsa", &read_only);
+
+    assert_eq!(send(&router, Method::GET, JANE, Some(&token)).await.0, StatusCode::OK);
+    for method in [Method::DELETE, Method::PATCH] {
+        let (status, headers, problem) = send(&router, method.clone(), JANE, Some(&token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{method}");
+        assert_eq!(problem["type"], "/problems/insufficient-scope");
+        assert_eq!(problem["detail"], "The operation needs the `users:write` scope.");
+        assert_eq!(
+            headers[header::WWW_AUTHENTICATE],
+            r#"Bearer realm="api", error="insufficient_scope", scope="users:write""#
+        );
+    }
+}
+
+#[tokio::test]
+async fn callers_only_reach_their_own_businesses() {
+    let router = router();
+    let mut outsider = claims();
+    outsider["businesses"] = json!([OTHER_BUSINESS]);
+    let token = token(Algorithm::ES256, "ec", &outsider);
+
+    for (method, uri) in [(Method::GET, JANE.to_string()), (Method::GET, format!("{USERS}/search?q=jane")), (Method::DELETE, JANE.to_string())] {
+        let (status, _, problem) = send(&router, method.clone(), &uri, Some(&token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{method} {uri}");
+        assert_eq!(problem["type"], "/problems/not-a-member");
+        assert_eq!(problem["detail"], format!("The caller is not a member of business {BUSINESS}."));
+    }
+    let (status, _, _) = send(&router, Method::GET, &format!("/business/{OTHER_BUSINESS}/users"), Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn callers_only_list_their_own_businesses() {
+    let router = router();
+    let mut member = claims();
+    member["scope"] = json!("businesses:read");
+    let token = token(Algorithm::ES256, "ec", &member);
+
+    let (status, _, businesses) = send(&router, Method::GET, "/business", Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let ids: Vec<_> = businesses.as_array().unwrap().iter().map(|business| business["uuid"].clone()).collect();
+    assert_eq!(ids, [json!(BUSINESS)]);
+    let (status, _, _) = send(&router, Method::GET, &format!("/business/{OTHER_BUSINESS}"), Some(&token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn handlers_can_extract_the_claims() {
+    let state = AppState::new(InMemoryStore::default())
+        .with_auth(Authenticator::new(&config(JwksSource::File(jwks_file()))));
+    let router = Router::new()
+        .route("/me", get(|claims: Claims| async move { Json(json!({ "sub": claims.sub, "scopes": claims.scopes().collect::<Vec<_>>() })) }))
+        .route_layer(axum::middleware::from_fn_with_state(state, authenticate));
+
+    let (status, _, body) = send(&router, Method::GET, "/me", Some(&token(Algorithm::ES256, "ec", &claims()))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "sub": "user-1", "scopes": ["users:read", "users:write", "api-keys:read", "api-keys:write"] }));
+}
+
+#[tokio::test]
+async fn jwks_is_fetched_once_per_ttl() {
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let provider = Router::new()
+        .route(
+            "/.well-known/jwks.json",
+            get(|State(fetches): State<Arc<AtomicUsize>>| async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                Json(jwks())
+            }),
+        )
+        .with_state(fetches.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/.well-known/jwks.json", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, provider).await.unwrap() });
+
+    let authenticator = Authenticator::new(&config(url.parse().unwrap()));
+    authenticator.verify(&token(Algorithm::RS256, "rsa", &claims())).await.unwrap();
+    authenticator.verify(&token(Algorithm::ES256, "ec", &claims())).await.unwrap();
+    // A key missing from a freshly fetched set does not trigger another fetch.
+    authenticator.verify(&token(Algorithm::RS256, "rotated", &claims())).await.unwrap_err();
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+    let authenticator = Authenticator::new(&AuthConfig { jwks_ttl: 0, ..config(url.parse().unwrap()) });
+    authenticator.verify(&token(Algorithm::RS256, "rsa", &claims())).await.unwrap();
+    authenticator.verify(&token(Algorithm::RS256, "rsa", &claims())).await.unwrap();
+    assert_eq!(fetches.load(Ordering::SeqCst), 3);
+}
+
+const API_KEYS: &str = "/business/7c9e6679-7425-40de-944b-e07fc1f90ae7/api-keys";
+
+/// Creates an API key with a bearer token, returning the response.
+async fn create_key(router: &Router, body: Value) -> (StatusCode, axum::http::HeaderMap, Value) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(API_KEYS)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token(Algorithm::RS256, "rsa", &claims())))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    respond(router, request).await
+}
+
+async fn with_key(router: &Router, method: Method, uri: &str, key: &str) -> (StatusCode, axum::http::HeaderMap, Value) {
+    let request = Request::builder().method(method).uri(uri).header("x-api-key", key).body(Body::empty()).unwrap();
+    respond(router, request).await
+}
+
+#[tokio::test]
+async fn api_keys_authenticate_within_their_business_and_scopes() {
+    let router = router();
+    let (status, headers, created) = create_key(&router, json!({ "name": "Billing export", "scopes": ["users:read"] })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(headers[header::CACHE_CONTROL], "no-store");
+    let key = created["key"].as_str().unwrap();
+    let prefix = created["prefix"].as_str().unwrap();
+    assert!(key.starts_with(&format!("{prefix}.")));
+    assert!(prefix.starts_with("ak_"));
+
+    assert_eq!(with_key(&router, Method::GET, JANE, key).await.0, StatusCode::OK);
+    let (status, _, problem) = with_key(&router, Method::DELETE, JANE, key).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["type"], "/problems/insufficient-scope");
+    let (status, _, problem) = with_key(&router, Method::GET, &format!("/business/{OTHER_BUSINESS}/users"), key).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["type"], "/problems/not-a-member");
+
+    let wrong_secret = format!("{prefix}.{}", "A".repeat(43));
+    for key in [wrong_secret.as_str(), "ak_000000000000.secret", "not a key"] {
+        let (status, _, problem) = with_key(&router, Method::GET, JANE, key).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{key}");
+        assert_eq!(problem["type"], "/problems/invalid-api-key");
+        assert_eq!(problem["detail"], "The API key is not valid: it does not exist or has been revoked.");
+    }
+}
+
+#[tokio::test]
+async fn api_keys_are_listed_without_secrets_and_revoked() {
+    let router = router();
+    let (_, _, created) = create_key(&router, json!({ "name": "Billing export", "scopes": ["users:read", "api-keys:read"] })).await;
+    let key = created["key"].as_str().unwrap();
+    let prefix = created["prefix"].as_str().unwrap();
+
+    let (status, _, listed) = with_key(&router, Method::GET, API_KEYS, key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["prefix"], prefix);
+    assert_eq!(listed[0]["scopes"], json!(["users:read", "api-keys:read"]));
+    assert!(listed[0]["lastUsedAt"].is_string());
+    assert!(listed[0].get("key").is_none());
+    assert!(!listed.to_string().contains(key.split_once('.').unwrap().1));
+
+    // Revoking needs `api-keys:write`, which this key lacks.
+    let uri = format!("{API_KEYS}/{prefix}");
+    assert_eq!(with_key(&router, Method::DELETE, &uri, key).await.0, StatusCode::FORBIDDEN);
+    let bearer = token(Algorithm::RS256, "rsa", &claims());
+    assert_eq!(send(&router, Method::DELETE, &uri, Some(&bearer)).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(with_key(&router, Method::GET, JANE, key).await.0, StatusCode::UNAUTHORIZED);
+    let (status, _, problem) = send(&router, Method::DELETE, &uri, Some(&bearer)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(problem["type"], "/problems/api-key-not-found");
+}
+
+#[tokio::test]
+async fn api_keys_are_checked_when_created_and_expire() {
+    let router = router();
+    let (status, _, problem) = create_key(&router, json!({ "name": " ", "scopes": ["users:admin"], "expiresAt": "tomorrow" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        problem["errors"],
+        json!({
+            "name": "must not start or end with whitespace or contain control characters",
+            "scopes": "item 1 must be one of `users:read`, `users:write`, `api-keys:read`, `api-keys:write`, `businesses:read`, `businesses:write`",
+            "expiresAt": "must be an RFC 3339 date-time such as 2030-01-01T00:00:00Z",
+        })
+    );
+    let (status, _, problem) = create_key(&router, json!({ "name": "Old", "scopes": ["users:read"], "expiresAt": "2020-01-01T00:00:00Z" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["errors"], json!({ "expiresAt": "must be in the future" }));
+
+    // A caller cannot hand out scopes it does not hold.
+    let mut reader = claims();
+    reader["scope"] = json!("api-keys:write users:read");
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(API_KEYS)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token(Algorithm::RS256, "rsa", &reader)))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({ "name": "Writer", "scopes": ["users:write"] }).to_string()))
+        .unwrap();
+    let (status, _, problem) = respond(&router, request).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["detail"], "The operation needs the `users:write` scope.");
+
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(1);
+    let (_, _, created) = create_key(&router, json!({ "name": "Brief", "scopes": ["users:read"], "expiresAt": expires_at })).await;
+    let key = created["key"].as_str().unwrap();
+    assert_eq!(with_key(&router, Method::GET, JANE, key).await.0, StatusCode::OK);
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let (status, _, problem) = with_key(&router, Method::GET, JANE, key).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(problem["detail"], "The API key is not valid: it has expired.");
+}
+
+/// A key store where every prefix is already taken.
+struct FullKeyStore;
+
+#[async_trait]
+impl ApiKeyRepository for FullKeyStore {
+    async fn find_key(&self, _: &str) -> Result<Option<StoredApiKey>, RepositoryError> {
+        Ok(None)
+    }
+
+    async fn list_keys(&self, _: Uuid) -> Result<Vec<ApiKey>, RepositoryError> {
+        Ok(Vec::new())
+    }
+
+    async fn create_key(&self, _: StoredApiKey) -> Result<(), RepositoryError> {
+        Err(RepositoryError::Conflict)
+    }
+
+    async fn touch_key(&self, _: &str, _: DateTime<Utc>) -> Result<(), RepositoryError> {
+        Err(RepositoryError::NotFound)
+    }
+
+    async fn delete_key(&self, _: Uuid, _: &str) -> Result<(), RepositoryError> {
+        Err(RepositoryError::NotFound)
+    }
+}
+
+#[tokio::test]
+async fn api_key_creation_gives_up_on_taken_prefixes() {
+    let users = InMemoryStore::from_json(include_str!("fixtures/users.json")).unwrap();
+    let state = AppState::new(users).with_api_keys(Arc::new(FullKeyStore));
+    let router = app(state.with_auth(Authenticator::new(&config(JwksSource::File(jwks_file())))));
+    let (status, _, problem) = create_key(&router, json!({ "name": "Billing export", "scopes": ["users:read"] })).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(problem["type"], "/problems/internal-error");
+}
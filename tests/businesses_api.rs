@@ -0,0 +1,218 @@
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use rust_lambda_api_poc::repository::memory::InMemoryStore;
+use rust_lambda_api_poc::{app, AppState};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+const BUSINESS: &str = "7c9e6679-7425-40de-944b-e07fc1f90ae7";
+
+fn router() -> Router {
+    let users = InMemoryStore::from_json(include_str!("fixtures/users.json")).unwrap();
+    app(AppState::new(users).with_anonymous_access())
+}
+
+async fn send(router: &Router, method: Method, uri: &str, body: Option<(&str, Value)>) -> (StatusCode, axum::http::HeaderMap, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    let body = match body {
+        Some((content_type, body)) => {
+            request = request.header(header::CONTENT_TYPE, content_type);
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = router.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    (parts.status, parts.headers, body)
+}
+
+async fn create(router: &Router, body: Value) -> (StatusCode, axum::http::HeaderMap, Value) {
+    send(router, Method::POST, "/business", Some(("application/json", body))).await
+}
+
+async fn patch(router: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let (status, _, body) = send(router, Method::PATCH, uri, Some(("application/merge-patch+json", body))).await;
+    (status, body)
+}
+
+#[tokio::test]
+async fn create_business_returns_created_with_location_and_default_settings() {
+    let router = router();
+    let (status, headers, business) = create(&router, json!({ "name": "Acme Ltd", "slug": "acme-ltd" })).await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(business["status"], "active");
+    assert_eq!(business["settings"], json!({ "timeZone": "UTC", "locale": "en" }));
+    assert_eq!(business["createdAt"], business["updatedAt"]);
+    let location = headers[header::LOCATION].to_str().unwrap();
+    assert_eq!(location, format!("/business/{}", business["uuid"].as_str().unwrap()));
+
+    let (status, _, fetched) = send(&router, Method::GET, location, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched, business);
+}
+
+#[tokio::test]
+async fn businesses_users_were_stored_under_exist() {
+    let (status, _, business) = send(&router(), Method::GET, &format!("/business/{BUSINESS}"), None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(business["slug"], BUSINESS);
+    assert_eq!(business["status"], "active");
+}
+
+#[tokio::test]
+async fn slugs_are_unique_across_businesses() {
+    let router = router();
+    create(&router, json!({ "name": "Acme Ltd", "slug": "acme" })).await;
+    let (status, _, problem) = create(&router, json!({ "name": "Acme Group", "slug": "acme" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(problem["type"], "/problems/slug-taken");
+
+    let (_, _, other) = create(&router, json!({ "name": "Globex", "slug": "globex" })).await;
+    let uri = format!("/business/{}", other["uuid"].as_str().unwrap());
+    let (status, problem) = patch(&router, &uri, json!({ "slug": "acme" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(problem["type"], "/problems/slug-taken");
+}
+
+#[tokio::test]
+async fn create_business_reports_bad_settings_by_path() {
+    let (status, _, problem) = create(
+        &router(),
+        json!({ "name": " Acme", "slug": "Acme Ltd", "settings": { "timeZone": "Europe/London!", "locale": "English" } }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let errors = problem["errors"].as_object().unwrap();
+    let mut fields: Vec<_> = errors.keys().map(String::as_str).collect();
+    fields.sort();
+    assert_eq!(fields, ["name", "settings.locale", "settings.timeZone", "slug"]);
+}
+
+#[tokio::test]
+async fn patch_business_merges_settings() {
+    let router = router();
+    let uri = format!("/business/{BUSINESS}");
+    let (status, business) = patch(&router, &uri, json!({ "name": "Acme Ltd", "settings": { "timeZone": "Europe/Paris" } })).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(business["name"], "Acme Ltd");
+    assert_eq!(business["slug"], BUSINESS);
+    assert_eq!(business["settings"], json!({ "timeZone": "Europe/Paris", "locale": "en" }));
+    assert_ne!(business["updatedAt"], business["createdAt"]);
+
+    let (status, problem) = patch(&router, &uri, json!({ "uuid": "00000000-0000-0000-0000-000000000001", "status": "archived" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let errors = problem["errors"].as_object().unwrap();
+    assert!(errors.contains_key("uuid"));
+    assert!(errors.contains_key("status"));
+}
+
+#[tokio::test]
+async fn archived_business_is_listed_by_status_and_read_only_until_restored() {
+    let router = router();
+    let uri = format!("/business/{BUSINESS}");
+    let (status, _, business) = send(&router, Method::POST, &format!("{uri}/archive"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(business["status"], "archived");
+    let (_, _, again) = send(&router, Method::POST, &format!("{uri}/archive"), None).await;
+    assert_eq!(again, business);
+
+    let (status, problem) = patch(&router, &uri, json!({ "name": "Acme Ltd" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(problem["type"], "/problems/business-archived");
+
+    let (_, _, archived) = send(&router, Method::GET, "/business?status=archived", None).await;
+    assert_eq!(archived, json!([business]));
+    let (_, _, active) = send(&router, Method::GET, "/business?status=active", None).await;
+    assert!(active.as_array().unwrap().iter().all(|business| business["uuid"] != BUSINESS));
+
+    let (status, _, business) = send(&router, Method::POST, &format!("{uri}/restore"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(business["status"], "active");
+    let (status, _) = patch(&router, &uri, json!({ "name": "Acme Ltd" })).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn archive(router: &Router) {
+    let (status, _, _) = send(router, Method::POST, &format!("/business/{BUSINESS}/archive"), None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn users_of_an_archived_business_are_read_only() {
+    let router = router();
+    let users = format!("/business/{BUSINESS}/users");
+    let jane = format!("{users}/550e8400-e29b-41d4-a716-446655440000");
+    let (_, _, user) = send(&router, Method::GET, &jane, None).await;
+    archive(&router).await;
+
+    let (status, _, _) = send(&router, Method::GET, &jane, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) = send(&router, Method::GET, &users, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let new_user = json!({ "firstName": "Ada", "lastName": "Lovelace", "email": "ada@example.com" });
+    let replacement = json!({ "firstName": "Jane", "lastName": "Roe", "email": user["email"], "enabled": true, "activated": true });
+    for (method, uri, body) in [
+        (Method::POST, users.clone(), Some(("application/json", new_user))),
+        (Method::PUT, jane.clone(), Some(("application/json", replacement))),
+        (Method::PATCH, jane.clone(), Some(("application/merge-patch+json", json!({ "lastName": "Roe" })))),
+        (Method::DELETE, jane.clone(), None),
+        (Method::DELETE, format!("{jane}?hard=true"), None),
+        (Method::POST, format!("{jane}/restore"), None),
+    ] {
+        let (status, _, problem) = send(&router, method.clone(), &uri, body).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{method} {uri}");
+        assert_eq!(problem["type"], "/problems/business-archived", "{method} {uri}");
+    }
+    let (_, _, unchanged) = send(&router, Method::GET, &jane, None).await;
+    assert_eq!(unchanged, user);
+}
+
+#[tokio::test]
+async fn api_keys_of_an_archived_business_are_read_only() {
+    let router = router();
+    let keys = format!("/business/{BUSINESS}/api-keys");
+    let new_key = json!({ "name": "Billing export", "scopes": ["users:read"] });
+    let (status, _, key) = send(&router, Method::POST, &keys, Some(("application/json", new_key.clone()))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    archive(&router).await;
+
+    let (status, _, listed) = send(&router, Method::GET, &keys, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+
+    let revoke = format!("{keys}/{}", key["prefix"].as_str().unwrap());
+    for (method, uri, body) in [
+        (Method::POST, keys.clone(), Some(("application/json", new_key))),
+        (Method::DELETE, revoke, None),
+    ] {
+        let (status, _, problem) = send(&router, method.clone(), &uri, body).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{method} {uri}");
+        assert_eq!(problem["type"], "/problems/business-archived", "{method} {uri}");
+    }
+    let (_, _, still_listed) = send(&router, Method::GET, &keys, None).await;
+    assert_eq!(still_listed, listed);
+}
+
+#[tokio::test]
+async fn missing_business_is_a_problem_document() {
+    let router = router();
+    for (method, uri) in [
+        (Method::GET, "/business/00000000-0000-0000-0000-000000000001"),
+        (Method::POST, "/business/00000000-0000-0000-0000-000000000001/archive"),
+        (Method::GET, "/business/00000000-0000-0000-0000-000000000001/users"),
+        (Method::GET, "/business/00000000-0000-0000-0000-000000000001/api-keys"),
+    ] {
+        let (status, headers, problem) = send(&router, method, uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+        assert_eq!(headers[header::CONTENT_TYPE], "application/problem+json");
+        assert_eq!(problem["type"], "/problems/business-not-found", "{uri}");
+    }
+}
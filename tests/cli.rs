@@ -0,0 +1,34 @@
+use std::process::Command;
+
+use rust_lambda_api_poc::docs::SpecFormat;
+use rust_lambda_api_poc::openapi;
+
+fn cli() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_rust-lambda-api-poc"))
+}
+
+#[test]
+fn openapi_writes_the_spec_to_a_file() {
+    let out = std::env::temp_dir().join(format!("openapi-{}.yaml", std::process::id()));
+    let status = cli().args(["openapi", "--format", "yaml", "--out"]).arg(&out).status().unwrap();
+    assert!(status.success());
+
+    let written = std::fs::read_to_string(&out).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert_eq!(written, SpecFormat::Yaml.render(&openapi()).unwrap());
+}
+
+#[test]
+fn openapi_defaults_to_json_on_stdout() {
+    let output = cli().arg("openapi").output().unwrap();
+    assert!(output.status.success());
+    let spec: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(spec, serde_json::to_value(openapi()).unwrap());
+}
+
+#[test]
+fn openapi_rejects_unknown_formats() {
+    let output = cli().args(["openapi", "--format", "xml"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("expected `json` or `yaml`"));
+}
@@ -0,0 +1,256 @@
+//! Behaviour every repository implementation must share.
+#![allow(dead_code)]
+
+use rust_lambda_api_poc::repository::query::{sort_key, SortOrder, UserSort};
+use rust_lambda_api_poc::api_keys::{ApiKey, StoredApiKey};
+use rust_lambda_api_poc::businesses::{Business, BusinessSettings, BusinessStatus};
+use rust_lambda_api_poc::policy::Scope;
+use rust_lambda_api_poc::repository::{ApiKeyRepository, BusinessRepository, RepositoryError, UserQuery, UserRepository};
+use rust_lambda_api_poc::users::User;
+use serde::Deserialize;
+use uuid::{uuid, Uuid};
+
+pub const BUSINESS: Uuid = uuid!("7c9e6679-7425-40de-944b-e07fc1f90ae7");
+pub const OTHER_BUSINESS: Uuid = uuid!("3f2504e0-4f89-11d3-9a0c-0305e82c3301");
+pub const JANE: Uuid = uuid!("550e8400-e29b-41d4-a716-446655440000");
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SeedUser {
+    business_id: Uuid,
+    #[serde(flatten)]
+    user: User,
+}
+
+/// Inserts `tests/fixtures/users.json` through the repository itself.
+pub async fn seed(users: &dyn UserRepository) {
+    let seed: Vec<SeedUser> = serde_json::from_str(include_str!("../fixtures/users.json")).unwrap();
+    for SeedUser { business_id, user } in seed {
+        users.create(business_id, user).await.unwrap();
+    }
+}
+
+/// Reads the user, changes it and saves it over what was read, as the handlers do.
+pub async fn edit(users: &dyn UserRepository, user_id: Uuid, change: impl FnOnce(&mut User)) -> Result<User, RepositoryError> {
+    let current = users.get(BUSINESS, user_id).await.unwrap().unwrap();
+    let mut user = current.clone();
+    change(&mut user);
+    users.update(BUSINESS, user, &current).await
+}
+
+pub async fn reads_are_scoped_to_the_business(users: &dyn UserRepository) {
+    assert_eq!(users.get(BUSINESS, JANE).await.unwrap().unwrap().first_name, "Jane");
+    assert_eq!(users.get(OTHER_BUSINESS, JANE).await.unwrap(), None);
+    assert_eq!(users.list(BUSINESS).await.unwrap().len(), 2);
+    assert_eq!(users.list(OTHER_BUSINESS).await.unwrap().len(), 1);
+}
+
+pub async fn create_update_delete_round_trip(users: &dyn UserRepository) {
+    let jane = users.get(BUSINESS, JANE).await.unwrap().unwrap();
+
+    assert!(matches!(
+        users.create(BUSINESS, jane.clone()).await,
+        Err(RepositoryError::Conflict)
+    ));
+    users.create(OTHER_BUSINESS, jane.clone()).await.unwrap();
+
+    let jane = edit(users, JANE, |jane| jane.enabled = false).await.unwrap();
+    assert_eq!(users.get(BUSINESS, JANE).await.unwrap(), Some(jane));
+
+    users.delete(BUSINESS, JANE).await.unwrap();
+    assert_eq!(users.get(BUSINESS, JANE).await.unwrap(), None);
+    assert!(matches!(users.delete(BUSINESS, JANE).await, Err(RepositoryError::NotFound)));
+    assert!(users.get(OTHER_BUSINESS, JANE).await.unwrap().is_some());
+}
+
+pub async fn emails_are_unique_per_business(users: &dyn UserRepository) {
+    let mut duplicate = users.get(BUSINESS, JANE).await.unwrap().unwrap();
+    duplicate.uuid = Uuid::new_v4();
+    duplicate.email = duplicate.email.to_uppercase();
+    assert!(matches!(
+        users.create(BUSINESS, duplicate.clone()).await,
+        Err(RepositoryError::EmailTaken)
+    ));
+
+    let john = users.list(BUSINESS).await.unwrap().into_iter().find(|user| user.uuid != JANE).unwrap();
+    let result = edit(users, john.uuid, |john| john.email = "jane.doe@example.com".to_string()).await;
+    assert!(matches!(result, Err(RepositoryError::EmailTaken)));
+
+    users.create(OTHER_BUSINESS, duplicate).await.unwrap();
+}
+
+/// Emails are compared ignoring ASCII case only, so a non-ASCII case change is a new address
+/// that can be saved over the old one.
+pub async fn non_ascii_email_case_changes(users: &dyn UserRepository) {
+    edit(users, JANE, |jane| jane.email = "zoë@example.com".to_string()).await.unwrap();
+    let jane = edit(users, JANE, |jane| jane.email = "ZOË@example.com".to_string()).await.unwrap();
+    assert_eq!(users.get(BUSINESS, JANE).await.unwrap(), Some(jane));
+
+    let zoe = users.list(BUSINESS).await.unwrap().into_iter().find(|user| user.uuid != JANE).unwrap().uuid;
+    let result = edit(users, zoe, |zoe| zoe.email = "ZoË@EXAMPLE.com".to_string()).await;
+    assert!(matches!(result, Err(RepositoryError::EmailTaken)));
+    edit(users, zoe, |zoe| zoe.email = "zoë@example.com".to_string()).await.unwrap();
+}
+
+pub async fn deleted_at_round_trips(users: &dyn UserRepository) {
+    let deleted_at = "2025-03-01T12:30:00.123456Z".parse().unwrap();
+
+    edit(users, JANE, |jane| jane.deleted_at = Some(deleted_at)).await.unwrap();
+    assert_eq!(users.get(BUSINESS, JANE).await.unwrap().unwrap().deleted_at, Some(deleted_at));
+
+    edit(users, JANE, |jane| jane.deleted_at = None).await.unwrap();
+    assert_eq!(users.get(BUSINESS, JANE).await.unwrap().unwrap().deleted_at, None);
+}
+
+/// An update made from a user read before another update is refused, not saved over it.
+pub async fn concurrent_updates_are_refused(users: &dyn UserRepository) {
+    let read_first = users.get(BUSINESS, JANE).await.unwrap().unwrap();
+    let read_second = read_first.clone();
+
+    let first = User { first_name: "Janet".to_string(), ..read_first.clone() };
+    users.update(BUSINESS, first.clone(), &read_first).await.unwrap();
+    let second = User { enabled: !read_second.enabled, ..read_second.clone() };
+    assert!(matches!(
+        users.update(BUSINESS, second, &read_second).await,
+        Err(RepositoryError::ConcurrentUpdate)
+    ));
+    assert_eq!(users.get(BUSINESS, JANE).await.unwrap(), Some(first.clone()));
+
+    let missing = User { uuid: Uuid::new_v4(), ..first.clone() };
+    assert!(matches!(users.update(BUSINESS, missing.clone(), &missing).await, Err(RepositoryError::NotFound)));
+}
+
+/// Pages through the business one user at a time, as the list endpoint does.
+async fn pages(users: &dyn UserRepository, mut query: UserQuery) -> Vec<String> {
+    let mut names = Vec::new();
+    loop {
+        let page = users.query(BUSINESS, &query).await.unwrap();
+        let Some(user) = page.first() else {
+            return names;
+        };
+        names.push(format!("{} {}", user.first_name, user.last_name));
+        query.after = Some(sort_key(user, query.sort));
+    }
+}
+
+pub async fn query_filters_sorts_and_pages(users: &dyn UserRepository) {
+    for (first_name, last_name, email, created_at) in [
+        ("anna", "doe", "anna@example.com", "2025-02-01T00:00:00.5Z"),
+        ("Zoë", "Adams", "zoe_adams@example.com", "2024-12-31T23:59:59Z"),
+    ] {
+        let user = User {
+            uuid: Uuid::new_v4(),
+            first_name: first_name.to_string(),
+            last_name: last_name.to_string(),
+            email: email.to_string(),
+            enabled: false,
+            activated: true,
+            created_at: created_at.parse().unwrap(),
+            deleted_at: None,
+        };
+        users.create(BUSINESS, user).await.unwrap();
+    }
+    let john = users.list(BUSINESS).await.unwrap().into_iter().find(|user| user.first_name == "John").unwrap();
+    edit(users, john.uuid, |john| john.deleted_at = Some("2025-03-01T00:00:00Z".parse().unwrap())).await.unwrap();
+
+    let query = UserQuery {
+        enabled: None,
+        activated: None,
+        email_prefix: None,
+        sort: UserSort::Name,
+        order: SortOrder::Asc,
+        after: None,
+        limit: 1,
+    };
+    assert_eq!(pages(users, query.clone()).await, ["Zoë Adams", "anna doe", "Jane Doe"]);
+    assert_eq!(
+        pages(users, UserQuery { order: SortOrder::Desc, ..query.clone() }).await,
+        ["Jane Doe", "anna doe", "Zoë Adams"]
+    );
+    assert_eq!(
+        pages(users, UserQuery { sort: UserSort::CreatedAt, ..query.clone() }).await,
+        ["Zoë Adams", "Jane Doe", "anna doe"]
+    );
+    assert_eq!(pages(users, UserQuery { enabled: Some(true), ..query.clone() }).await, ["Jane Doe"]);
+    assert_eq!(
+        pages(users, UserQuery { email_prefix: Some("ZOE_".to_string()), ..query.clone() }).await,
+        ["Zoë Adams"]
+    );
+    assert!(pages(users, UserQuery { email_prefix: Some("zoe%".to_string()), ..query.clone() }).await.is_empty());
+
+    let first_two = users.query(BUSINESS, &UserQuery { limit: 2, ..query }).await.unwrap();
+    assert_eq!(first_two.len(), 3, "returns one extra user to signal another page");
+}
+
+pub async fn api_keys_round_trip(keys: &dyn ApiKeyRepository) {
+    let created_at = "2025-01-01T09:30:00Z".parse().unwrap();
+    let key = StoredApiKey {
+        business_id: BUSINESS,
+        api_key: ApiKey {
+            prefix: "ak_3f9c2a71b04e".to_string(),
+            name: "Billing export".to_string(),
+            scopes: vec![Scope::UsersRead, Scope::UsersWrite],
+            created_at,
+            expires_at: Some("2030-01-01T00:00:00Z".parse().unwrap()),
+            last_used_at: None,
+        },
+        secret_hash: "hash".to_string(),
+    };
+    keys.create_key(key.clone()).await.unwrap();
+    assert!(matches!(keys.create_key(key.clone()).await, Err(RepositoryError::Conflict)));
+    assert_eq!(keys.find_key("ak_3f9c2a71b04e").await.unwrap(), Some(key.clone()));
+    assert_eq!(keys.find_key("ak_000000000000").await.unwrap(), None);
+    assert_eq!(keys.list_keys(BUSINESS).await.unwrap(), std::slice::from_ref(&key.api_key));
+    assert!(keys.list_keys(OTHER_BUSINESS).await.unwrap().is_empty());
+
+    let used_at = "2025-01-02T10:00:00Z".parse().unwrap();
+    keys.touch_key("ak_3f9c2a71b04e", used_at).await.unwrap();
+    assert_eq!(keys.list_keys(BUSINESS).await.unwrap()[0].last_used_at, Some(used_at));
+    assert_eq!(keys.find_key("ak_3f9c2a71b04e").await.unwrap().unwrap().api_key.last_used_at, Some(used_at));
+
+    assert!(matches!(keys.delete_key(OTHER_BUSINESS, "ak_3f9c2a71b04e").await, Err(RepositoryError::NotFound)));
+    keys.delete_key(BUSINESS, "ak_3f9c2a71b04e").await.unwrap();
+    assert_eq!(keys.find_key("ak_3f9c2a71b04e").await.unwrap(), None);
+    assert!(matches!(keys.delete_key(BUSINESS, "ak_3f9c2a71b04e").await, Err(RepositoryError::NotFound)));
+    assert!(matches!(keys.touch_key("ak_3f9c2a71b04e", used_at).await, Err(RepositoryError::NotFound)));
+}
+
+pub async fn businesses_round_trip(businesses: &dyn BusinessRepository) {
+    let created_at = "2025-01-01T09:30:00Z".parse().unwrap();
+    let mut acme = Business {
+        uuid: Uuid::new_v4(),
+        name: "Acme Ltd".to_string(),
+        slug: "acme-ltd".to_string(),
+        status: BusinessStatus::Active,
+        settings: BusinessSettings { time_zone: "Europe/London".to_string(), locale: "en-GB".to_string() },
+        created_at,
+        updated_at: created_at,
+    };
+    businesses.create_business(acme.clone()).await.unwrap();
+    assert!(matches!(businesses.create_business(acme.clone()).await, Err(RepositoryError::Conflict)));
+    let mut globex = Business { uuid: Uuid::new_v4(), name: "Globex".to_string(), ..acme.clone() };
+    assert!(matches!(businesses.create_business(globex.clone()).await, Err(RepositoryError::SlugTaken)));
+    globex.slug = "globex".to_string();
+    businesses.create_business(globex.clone()).await.unwrap();
+
+    assert_eq!(businesses.get_business(acme.uuid).await.unwrap(), Some(acme.clone()));
+    assert_eq!(businesses.get_business(Uuid::new_v4()).await.unwrap(), None);
+    let listed: Vec<_> = businesses.list_businesses().await.unwrap().into_iter().map(|business| business.uuid).collect();
+    let mut expected = vec![acme.uuid, globex.uuid];
+    expected.sort();
+    assert_eq!(listed.into_iter().filter(|uuid| expected.contains(uuid)).collect::<Vec<_>>(), expected);
+
+    // A changed slug frees the old one.
+    acme.slug = "acme-group".to_string();
+    acme.status = BusinessStatus::Archived;
+    acme.updated_at = "2025-02-01T12:00:00Z".parse().unwrap();
+    businesses.update_business(acme.clone()).await.unwrap();
+    assert_eq!(businesses.get_business(acme.uuid).await.unwrap(), Some(acme.clone()));
+    globex.slug = "acme-ltd".to_string();
+    businesses.update_business(globex.clone()).await.unwrap();
+    globex.slug = "acme-group".to_string();
+    assert!(matches!(businesses.update_business(globex.clone()).await, Err(RepositoryError::SlugTaken)));
+
+    let missing = Business { uuid: Uuid::new_v4(), slug: "missing".to_string(), ..acme };
+    assert!(matches!(businesses.update_business(missing).await, Err(RepositoryError::NotFound)));
+}
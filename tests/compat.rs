@@ -0,0 +1,82 @@
+use std::process::Command;
+
+use rust_lambda_api_poc::compat::{diff, parse};
+use rust_lambda_api_poc::openapi;
+use serde_json::{json, Value};
+
+fn fixture(name: &str) -> Value {
+    let path = format!("{}/tests/fixtures/openapi/{name}", env!("CARGO_MANIFEST_DIR"));
+    parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// The changes as `(breaking, "location: description")`, sorted for comparison.
+fn changes(baseline: &Value, current: &Value) -> Vec<(bool, String)> {
+    let mut changes: Vec<_> = diff(baseline, current).iter().map(|change| (change.breaking, change.to_string())).collect();
+    changes.sort();
+    changes
+}
+
+#[test]
+fn classifies_changes_between_fixture_specs() {
+    let mut expected = vec![
+        (true, "/legacy: path removed".to_string()),
+        (true, "DELETE /users/{userId}: operation removed".to_string()),
+        (true, "GET /users/{userId} parameter query tenant: required parameter added".to_string()),
+        (true, "GET /users/{userId} response 200.age: type widened, may now return `string`".to_string()),
+        (true, "GET /users/{userId} response 200.nickname: field removed".to_string()),
+        (true, "POST /users request.name: `maxLength` tightened".to_string()),
+        (true, "POST /users request.role: enum values removed: \"admin\"".to_string()),
+        (true, "POST /users request.team: required field added".to_string()),
+        (true, "POST /users response 201.age: type widened, may now return `string`".to_string()),
+        (true, "POST /users response 201.nickname: field removed".to_string()),
+        (false, "/health: path added".to_string()),
+        (false, "GET /users/{userId} parameter query expand: optional parameter added".to_string()),
+        (false, "GET /users/{userId} response 200.createdAt: field added".to_string()),
+        (false, "GET /users/{userId} response 404: response removed".to_string()),
+        (false, "POST /users request.locale: field added".to_string()),
+        (false, "POST /users response 201.createdAt: field added".to_string()),
+    ];
+    expected.sort();
+    assert_eq!(changes(&fixture("baseline.yaml"), &fixture("current.yaml")), expected);
+}
+
+#[test]
+fn reverse_direction_is_classified_from_the_other_side() {
+    let changes = changes(&fixture("current.yaml"), &fixture("baseline.yaml"));
+    assert!(changes.contains(&(false, "POST /users request.name: `maxLength` relaxed".to_string())));
+    assert!(changes.contains(&(true, "GET /users/{userId} parameter query tenant: parameter removed".to_string())));
+    assert!(changes.contains(&(false, "GET /users/{userId} response 200.age: type narrowed, no longer returns `string`".to_string())));
+}
+
+#[test]
+fn current_spec_has_no_changes_from_itself() {
+    let spec = serde_json::to_value(openapi()).unwrap();
+    assert_eq!(diff(&spec, &spec), vec![]);
+}
+
+fn cli_diff(baseline: &Value, allow: &[&str]) -> std::process::Output {
+    let path = std::env::temp_dir().join(format!("baseline-{}-{}.json", std::process::id(), allow.len()));
+    std::fs::write(&path, baseline.to_string()).unwrap();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rust-lambda-api-poc"));
+    command.args(["diff", "--baseline"]).arg(&path);
+    for location in allow {
+        command.args(["--allow", location]);
+    }
+    let output = command.output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    output
+}
+
+#[test]
+fn cli_fails_on_breaking_changes_unless_allowed() {
+    let mut baseline = serde_json::to_value(openapi()).unwrap();
+    baseline["paths"]["/legacy"] = json!({ "get": { "responses": { "200": { "description": "Legacy" } } } });
+
+    let output = cli_diff(&baseline, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("BREAKING  /legacy: path removed"));
+
+    let output = cli_diff(&baseline, &["/legacy"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("allowed  /legacy: path removed"));
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+
+use rust_lambda_api_poc::auth::JwksSource;
+use rust_lambda_api_poc::config::{Config, Storage};
+use rust_lambda_api_poc::docs::ScalarSource;
+use tracing_subscriber::filter::LevelFilter;
+
+fn load(file: Option<&str>, env: &[(&str, &str)]) -> Result<Config, String> {
+    let env: HashMap<String, String> = env.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+    let file = file.map(|contents| (Path::new("server.toml"), contents));
+    Config::from_sources(file, |key| env.get(key).cloned()).map_err(|error| error.to_string())
+}
+
+#[test]
+fn defaults_match_local_development() {
+    let config = load(None, &[]).unwrap();
+    assert_eq!(config, Config::default());
+    assert_eq!(config.host, IpAddr::V4(Ipv4Addr::LOCALHOST));
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.docs.path, "/api");
+    assert_eq!(config.docs.theme, "laserwave");
+    assert_eq!(config.storage, Storage::Memory { fixture: None });
+}
+
+const FILE: &str = r#"
+host = "0.0.0.0"
+port = 3000
+log_level = "debug"
+
+[docs]
+path = "/docs"
+theme = "moon"
+scalar = "cdn"
+
+[storage]
+backend = "sqlite"
+url = "sqlite://users.db"
+"#;
+
+#[test]
+fn file_sets_every_section() {
+    let config = load(Some(FILE), &[]).unwrap();
+    assert_eq!(config.host, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    assert_eq!(config.port, 3000);
+    assert_eq!(config.log_level, LevelFilter::DEBUG);
+    assert_eq!(config.docs.path, "/docs");
+    assert_eq!(config.docs.theme, "moon");
+    assert_eq!(config.docs.scalar, ScalarSource::Cdn);
+    assert!(config.docs.enabled);
+    assert_eq!(config.storage, Storage::Sqlite { url: "sqlite://users.db".to_string() });
+}
+
+#[test]
+fn environment_overrides_the_file() {
+    let env = [("PORT", "9000"), ("DOCS_ENABLED", "false"), ("DYNAMODB_TABLE", "users"), ("DATABASE_URL", "sqlite::memory:")];
+    let config = load(Some(FILE), &env).unwrap();
+    assert_eq!(config.port, 9000);
+    assert_eq!(config.host, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    assert!(!config.docs.enabled);
+    assert_eq!(config.storage, Storage::DynamoDb { table: "users".to_string(), endpoint: None });
+}
+
+#[test]
+fn invalid_values_are_reported_readably() {
+    assert_eq!(
+        load(None, &[("PORT", "80800")]).unwrap_err(),
+        "invalid PORT `80800`: number too large to fit in target type"
+    );
+    assert_eq!(
+        load(None, &[("DOCS_PATH", "api/")]).unwrap_err(),
+        "invalid docs path `api/`: expected an absolute path below `/` without a trailing slash, such as `/api`"
+    );
+    assert_eq!(
+        load(None, &[("DOCS_PATH", "/business")]).unwrap_err(),
+        "invalid docs path `/business`: must not be or fall under the API path `/business`"
+    );
+    assert!(load(None, &[("DOCS_PATH", "/business/docs")]).unwrap_err().starts_with("invalid docs path `/business/docs`"));
+    assert!(load(None, &[("DOCS_PATH", "/businesses")]).is_ok());
+    assert!(load(None, &[("SCALAR_THEME", "neon")]).unwrap_err().starts_with("invalid Scalar theme `neon`: expected one of"));
+    assert!(load(None, &[("HOST", "localhost:80")]).unwrap_err().starts_with("invalid HOST `localhost:80`"));
+}
+
+#[test]
+fn file_errors_name_the_file_and_key() {
+    let error = load(Some("port = \"eighty\"\n"), &[]).unwrap_err();
+    assert!(error.starts_with("invalid config file server.toml:"), "{error}");
+    assert!(error.contains("port"), "{error}");
+
+    let error = load(Some("[storage]\nbackend = \"postgres\"\n"), &[]).unwrap_err();
+    assert!(error.contains("unknown variant `postgres`"), "{error}");
+
+    let error = load(Some("prot = 80\n"), &[]).unwrap_err();
+    assert!(error.contains("unknown field `prot`"), "{error}");
+}
+
+#[test]
+fn auth_comes_from_the_file_or_the_environment() {
+    assert_eq!(load(None, &[]).unwrap().auth, None);
+
+    let file = "[auth]\nissuer = \"https://auth.example.com/\"\naudience = \"users-api\"\njwks = \"jwks.json\"\n";
+    let auth = load(Some(file), &[("AUTH_JWKS", "https://auth.example.com/.well-known/jwks.json")]).unwrap().auth.unwrap();
+    assert_eq!(auth.issuer, "https://auth.example.com/");
+    assert_eq!(auth.jwks, JwksSource::Url("https://auth.example.com/.well-known/jwks.json".to_string()));
+    assert_eq!(auth.jwks_ttl, 300);
+
+    let env = [("AUTH_ISSUER", "https://auth.example.com/"), ("AUTH_AUDIENCE", "users-api"), ("AUTH_JWKS", "jwks.json"), ("AUTH_JWKS_TTL", "60")];
+    let auth = load(None, &env).unwrap().auth.unwrap();
+    assert_eq!(auth.jwks, JwksSource::File("jwks.json".into()));
+    assert_eq!(auth.jwks_ttl, 60);
+
+    let error = load(None, &[("AUTH_ISSUER", "https://auth.example.com/")]).unwrap_err();
+    assert!(error.starts_with("missing AUTH_AUDIENCE:"), "{error}");
+}
+
+#[test]
+fn auth_is_only_disabled_explicitly() {
+    assert!(!load(None, &[]).unwrap().auth_disabled);
+    assert!(load(Some("auth_disabled = true\n"), &[]).unwrap().auth_disabled);
+    assert!(load(None, &[("AUTH_DISABLED", "true")]).unwrap().auth_disabled);
+
+    let env = [("AUTH_DISABLED", "true"), ("AUTH_ISSUER", "https://auth.example.com/"), ("AUTH_AUDIENCE", "users-api"), ("AUTH_JWKS", "jwks.json")];
+    let error = load(None, &env).unwrap_err();
+    assert!(error.starts_with("invalid auth_disabled `true`:"), "{error}");
+}
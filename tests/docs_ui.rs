@@ -0,0 +1,155 @@
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use axum::Router;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rust_lambda_api_poc::docs::{DocsConfig, ScalarSource, SCALAR_BUNDLE, SCALAR_VERSION};
+use rust_lambda_api_poc::repository::memory::InMemoryStore;
+use rust_lambda_api_poc::{app_with_docs, AppState};
+use sha2::{Digest, Sha512};
+use tower::ServiceExt;
+
+fn router(scalar: ScalarSource) -> Router {
+    app_with_docs(AppState::new(InMemoryStore::default()), &DocsConfig { scalar, ..Default::default() })
+}
+
+async fn get(router: &Router, uri: &str, if_none_match: Option<&str>) -> Response {
+    let mut request = Request::builder().uri(uri);
+    if let Some(etag) = if_none_match {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+}
+
+async fn text(response: Response) -> String {
+    String::from_utf8(to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap()
+}
+
+/// The `src` and `integrity` attributes of the page's bundle `<script>` tag.
+fn script(page: &str) -> (String, Option<String>) {
+    let tag = page.split("<script src=\"").nth(1).expect("a script tag with a src");
+    let src = tag.split('"').next().unwrap().to_string();
+    let integrity = tag.split("integrity=\"").nth(1).map(|rest| rest.split('"').next().unwrap().to_string());
+    (src, integrity)
+}
+
+#[test]
+fn bundle_is_the_pinned_version() {
+    let bundle = String::from_utf8_lossy(SCALAR_BUNDLE);
+    assert!(bundle.contains(&format!("@scalar/api-reference@{SCALAR_VERSION}")));
+}
+
+#[tokio::test]
+async fn docs_page_loads_the_embedded_bundle() {
+    let router = router(ScalarSource::Embedded);
+    let page = text(get(&router, "/api", None).await).await;
+    assert!(!page.contains("cdn.jsdelivr.net"));
+
+    let (src, integrity) = script(&page);
+    // Relative to the page at `/api`.
+    let response = get(&router, &format!("/{src}"), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/javascript; charset=utf-8");
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=31536000, immutable");
+    assert_eq!(response.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+
+    let digest = STANDARD.encode(Sha512::digest(SCALAR_BUNDLE));
+    assert_eq!(response.headers()["content-digest"], format!("sha-512=:{digest}:"));
+    assert_eq!(integrity, Some(format!("sha512-{digest}")));
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], SCALAR_BUNDLE);
+}
+
+#[tokio::test]
+async fn bundle_is_revalidated_by_etag() {
+    let router = router(ScalarSource::Embedded);
+    let (src, _) = script(&text(get(&router, "/api", None).await).await);
+    let uri = format!("/{src}");
+    let etag = get(&router, &uri, None).await.headers()[header::ETAG].to_str().unwrap().to_string();
+
+    let response = get(&router, &uri, Some(&etag)).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::ETAG], etag.as_str());
+    assert!(to_bytes(response.into_body(), usize::MAX).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn cdn_is_opt_in() {
+    let router = router(ScalarSource::Cdn);
+    let page = text(get(&router, "/api", None).await).await;
+
+    let (src, _) = script(&page);
+    assert_eq!(
+        src,
+        format!("https://cdn.jsdelivr.net/npm/@scalar/api-reference@{SCALAR_VERSION}/dist/browser/standalone.js")
+    );
+    let response = get(&router, &format!("/api/scalar/api-reference-{SCALAR_VERSION}.js"), None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn openapi_document_is_served_as_json_and_yaml() {
+    let router = router(ScalarSource::Embedded);
+    let response = get(&router, "/api/openapi.json", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    let json: serde_json::Value = serde_json::from_str(&text(response).await).unwrap();
+    let mut expected = serde_json::to_value(rust_lambda_api_poc::openapi()).unwrap();
+    expected["servers"] = serde_json::json!([{ "url": "/" }]);
+    assert_eq!(json, expected);
+
+    let response = get(&router, "/api/openapi.yaml", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/yaml");
+    assert!(text(response).await.starts_with("openapi: 3.1.0"));
+}
+
+#[tokio::test]
+async fn openapi_servers_follow_the_forwarded_host() {
+    let request = Request::builder()
+        .uri("/api/openapi.json")
+        .header(header::HOST, "internal:8080")
+        .header("x-forwarded-host", "api.example.com")
+        .header("x-forwarded-proto", "https")
+        .body(Body::empty())
+        .unwrap();
+    let response = router(ScalarSource::Embedded).oneshot(request).await.unwrap();
+    let json: serde_json::Value = serde_json::from_str(&text(response).await).unwrap();
+    assert_eq!(json["servers"], serde_json::json!([{ "url": "https://api.example.com" }]));
+}
+
+#[tokio::test]
+async fn openapi_document_is_revalidated_by_etag() {
+    let router = router(ScalarSource::Embedded);
+    for uri in ["/api/openapi.json", "/api/openapi.yaml"] {
+        let etag = get(&router, uri, None).await.headers()[header::ETAG].to_str().unwrap().to_string();
+        let response = get(&router, uri, Some(&etag)).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{uri}");
+    }
+    let json = get(&router, "/api/openapi.json", None).await;
+    let yaml = get(&router, "/api/openapi.yaml", None).await;
+    assert_ne!(json.headers()[header::ETAG], yaml.headers()[header::ETAG]);
+}
+
+#[tokio::test]
+async fn docs_follow_the_configured_path_and_theme() {
+    let config = DocsConfig { path: "/docs/users".to_string(), theme: "moon".to_string(), ..Default::default() };
+    let router = app_with_docs(AppState::new(InMemoryStore::default()), &config);
+
+    let page = text(get(&router, "/docs/users", None).await).await;
+    assert!(page.contains(r#"data-configuration='{"theme":"moon"}'"#));
+    let (src, _) = script(&page);
+    assert_eq!(get(&router, &format!("/docs/{src}"), None).await.status(), StatusCode::OK);
+    assert_eq!(get(&router, "/docs/users/openapi.json", None).await.status(), StatusCode::OK);
+    assert_eq!(get(&router, "/api", None).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn docs_can_be_disabled() {
+    let config = DocsConfig { enabled: false, ..Default::default() };
+    let router = app_with_docs(AppState::new(InMemoryStore::default()), &config);
+    for uri in ["/api", "/api/openapi.json"] {
+        assert_eq!(get(&router, uri, None).await.status(), StatusCode::NOT_FOUND, "{uri}");
+    }
+}
@@ -0,0 +1,77 @@
+//! Runs against DynamoDB Local, e.g. `docker run -p 8000:8000 amazon/dynamodb-local`, or another
+//! stand-in such as `moto_server -p 8000`,
+//! with `cargo test --test dynamodb_repository -- --ignored`.
+//! Set `DYNAMODB_ENDPOINT` to use another endpoint than `http://localhost:8000`.
+mod common;
+
+use aws_sdk_dynamodb::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_dynamodb::{Client, Config};
+use rust_lambda_api_poc::repository::dynamodb::DynamoDbStore;
+
+async fn repository() -> DynamoDbStore {
+    let endpoint = std::env::var("DYNAMODB_ENDPOINT").unwrap_or_else(|_| "http://localhost:8000".to_string());
+    let config = Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new("us-east-1"))
+        .endpoint_url(endpoint)
+        .credentials_provider(Credentials::new("local", "local", None, None, "tests"))
+        .build();
+    let users = DynamoDbStore::new(Client::from_conf(config), format!("users-{}", uuid::Uuid::new_v4()));
+    users.create_table().await.unwrap();
+    common::seed(&users).await;
+    users
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn reads_are_scoped_to_the_business() {
+    common::reads_are_scoped_to_the_business(&repository().await).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn create_update_delete_round_trip() {
+    common::create_update_delete_round_trip(&repository().await).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn emails_are_unique_per_business() {
+    common::emails_are_unique_per_business(&repository().await).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn non_ascii_email_case_changes() {
+    common::non_ascii_email_case_changes(&repository().await).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn deleted_at_round_trips() {
+    common::deleted_at_round_trips(&repository().await).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn concurrent_updates_are_refused() {
+    common::concurrent_updates_are_refused(&repository().await).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn query_filters_sorts_and_pages() {
+    common::query_filters_sorts_and_pages(&repository().await).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn api_keys_round_trip() {
+    common::api_keys_round_trip(&repository().await).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn businesses_round_trip() {
+    common::businesses_round_trip(&repository().await).await;
+}
@@ -0,0 +1,113 @@
+use axum::body::to_bytes;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use rust_lambda_api_poc::auth::Principal;
+use rust_lambda_api_poc::repository::memory::InMemoryStore;
+use rust_lambda_api_poc::{app, lambda, AppState};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+async fn handle(event: &str) -> (StatusCode, Value) {
+    let request = lambda_http::request::from_str(event).expect("valid Lambda event");
+    let users = InMemoryStore::from_json(include_str!("fixtures/users.json")).unwrap();
+    let response = lambda::service(app(AppState::new(users).with_anonymous_access())).oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn api_gateway_rest_event() {
+    let (status, user) = handle(include_str!("fixtures/apigw_rest_get_user.json")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["uuid"], "550e8400-e29b-41d4-a716-446655440000");
+    assert_eq!(user["firstName"], "Jane");
+}
+
+#[tokio::test]
+async fn api_gateway_http_event_with_stage_in_path() {
+    let (status, user) = handle(include_str!("fixtures/apigw_http_get_user.json")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["uuid"], "550e8400-e29b-41d4-a716-446655440000");
+    assert_eq!(user["firstName"], "Jane");
+}
+
+#[tokio::test]
+async fn application_load_balancer_event() {
+    let (status, user) = handle(include_str!("fixtures/alb_get_user.json")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["uuid"], "550e8400-e29b-41d4-a716-446655440000");
+    assert_eq!(user["firstName"], "Jane");
+}
+
+#[tokio::test]
+async fn openapi_servers_include_the_stage() {
+    let user_path = "/prod/business/7c9e6679-7425-40de-944b-e07fc1f90ae7/users/550e8400-e29b-41d4-a716-446655440000";
+    let event = include_str!("fixtures/apigw_http_get_user.json").replace(user_path, "/prod/api/openapi.json");
+    let (status, spec) = handle(&event).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(spec["servers"][0]["url"], "https://a1b2c3d4e5.execute-api.eu-west-1.amazonaws.com/prod");
+}
+
+const BUSINESS: &str = "7c9e6679-7425-40de-944b-e07fc1f90ae7";
+
+/// `event` as API Gateway sends it once `authorizer` has let the request through.
+fn authorized(event: &str, authorizer: Value) -> String {
+    let mut event: Value = serde_json::from_str(event).unwrap();
+    event["requestContext"]["authorizer"] = authorizer;
+    event.to_string()
+}
+
+#[tokio::test]
+async fn rest_api_cognito_authorizer_claims_are_the_principal() {
+    let authorizer = json!({ "claims": { "sub": "user-1", "custom:businesses": BUSINESS, "scope": "users:read" } });
+    let (status, user) = handle(&authorized(include_str!("fixtures/apigw_rest_get_user.json"), authorizer)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["firstName"], "Jane");
+}
+
+#[tokio::test]
+async fn http_api_jwt_authorizer_claims_are_checked_against_the_business() {
+    let authorizer = json!({ "jwt": {
+        "claims": { "sub": "user-1", "businesses": "[3f2504e0-4f89-11d3-9a0c-0305e82c3301]" },
+        "scopes": ["users:read"],
+    } });
+    let (status, problem) = handle(&authorized(include_str!("fixtures/apigw_http_get_user.json"), authorizer)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["type"], "/problems/not-a-member");
+}
+
+#[tokio::test]
+async fn http_api_lambda_authorizer_context_needs_the_scope() {
+    let authorizer = json!({ "lambda": { "sub": "user-1", "businesses": [BUSINESS], "scope": "users:write" } });
+    let (status, problem) = handle(&authorized(include_str!("fixtures/apigw_http_get_user.json"), authorizer)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["type"], "/problems/insufficient-scope");
+}
+
+#[tokio::test]
+async fn principal_extractor_reads_the_authorizer_context() {
+    let whoami = |principal: Principal| async move {
+        Json(json!({ "subject": principal.subject, "businesses": principal.businesses, "scopes": principal.scopes }))
+    };
+    let router = Router::new().route("/whoami", get(whoami));
+    let authorizer = json!({
+        "principalId": "machine-7",
+        "businesses": format!("{BUSINESS}, 3f2504e0-4f89-11d3-9a0c-0305e82c3301"),
+        "scope": "users:read api-keys:read",
+        "integrationLatency": 12,
+    });
+    let user_path = "/business/7c9e6679-7425-40de-944b-e07fc1f90ae7/users/550e8400-e29b-41d4-a716-446655440000";
+    let event = include_str!("fixtures/apigw_rest_get_user.json").replace(user_path, "/whoami");
+    let request = lambda_http::request::from_str(&authorized(&event, authorizer)).unwrap();
+    let response = lambda::service(router).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let principal: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(principal, json!({
+        "subject": "machine-7",
+        "businesses": ["3f2504e0-4f89-11d3-9a0c-0305e82c3301", BUSINESS],
+        "scopes": ["api-keys:read", "users:read"],
+    }));
+}
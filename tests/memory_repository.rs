@@ -0,0 +1,52 @@
+mod common;
+
+use rust_lambda_api_poc::repository::memory::InMemoryStore;
+
+fn repository() -> InMemoryStore {
+    InMemoryStore::from_json(include_str!("fixtures/users.json")).unwrap()
+}
+
+#[tokio::test]
+async fn reads_are_scoped_to_the_business() {
+    common::reads_are_scoped_to_the_business(&repository()).await;
+}
+
+#[tokio::test]
+async fn create_update_delete_round_trip() {
+    common::create_update_delete_round_trip(&repository()).await;
+}
+
+#[tokio::test]
+async fn emails_are_unique_per_business() {
+    common::emails_are_unique_per_business(&repository()).await;
+}
+
+#[tokio::test]
+async fn non_ascii_email_case_changes() {
+    common::non_ascii_email_case_changes(&repository()).await;
+}
+
+#[tokio::test]
+async fn deleted_at_round_trips() {
+    common::deleted_at_round_trips(&repository()).await;
+}
+
+#[tokio::test]
+async fn concurrent_updates_are_refused() {
+    common::concurrent_updates_are_refused(&repository()).await;
+}
+
+#[tokio::test]
+async fn query_filters_sorts_and_pages() {
+    common::query_filters_sorts_and_pages(&repository()).await;
+}
+
+#[tokio::test]
+async fn api_keys_round_trip() {
+    common::api_keys_round_trip(&repository()).await;
+}
+
+#[tokio::test]
+async fn businesses_round_trip() {
+    common::businesses_round_trip(&repository()).await;
+}
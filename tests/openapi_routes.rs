@@ -0,0 +1,174 @@
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use rust_lambda_api_poc::repository::memory::InMemoryStore;
+use rust_lambda_api_poc::businesses::UpdateBusiness;
+use rust_lambda_api_poc::validation::{LANGUAGE_TAG, SLUG, TIME_ZONE, TRIMMED_TEXT};
+use rust_lambda_api_poc::{app, openapi, AppState};
+use tower::ServiceExt;
+
+/// Status only the test fallbacks return, so it means the router had no match.
+const UNROUTED: StatusCode = StatusCode::IM_A_TEAPOT;
+
+/// Replaces every `{param}` segment with a non-nil UUID.
+fn concrete(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.starts_with('{') && segment.ends_with('}') {
+                "550e8400-e29b-41d4-a716-446655440000"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[tokio::test]
+async fn every_documented_operation_is_routed() {
+    let spec = openapi();
+    assert!(!spec.paths.paths.is_empty());
+
+    for (path, item) in &spec.paths.paths {
+        let operations = [
+            (Method::GET, &item.get),
+            (Method::POST, &item.post),
+            (Method::PUT, &item.put),
+            (Method::DELETE, &item.delete),
+            (Method::OPTIONS, &item.options),
+            (Method::HEAD, &item.head),
+            (Method::PATCH, &item.patch),
+            (Method::TRACE, &item.trace),
+        ];
+        for (method, _) in operations.iter().filter(|(_, operation)| operation.is_some()) {
+            let router = app(AppState::new(InMemoryStore::default()))
+                .fallback(|| async { UNROUTED })
+                .method_not_allowed_fallback(|| async { UNROUTED });
+            let request = Request::builder()
+                .method(method.clone())
+                .uri(concrete(path))
+                .body(Body::empty())
+                .unwrap();
+            let response = router.oneshot(request).await.unwrap();
+            assert_ne!(
+                response.status(),
+                UNROUTED,
+                "{method} {path} is documented but has no handler"
+            );
+        }
+    }
+}
+
+#[test]
+fn every_operation_documents_problem_responses() {
+    let spec = serde_json::to_value(openapi()).unwrap();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for (method, operation) in item.as_object().unwrap() {
+            let responses = operation["responses"].as_object().unwrap();
+            let errors: Vec<_> = responses
+                .iter()
+                .filter(|(status, _)| status.starts_with('4') || status.starts_with('5'))
+                .collect();
+            assert!(
+                errors.iter().any(|(status, _)| status.starts_with('4')),
+                "{method} {path} documents no client errors"
+            );
+            assert!(responses.contains_key("500"), "{method} {path} does not document 500");
+            for (status, response) in errors {
+                assert_eq!(
+                    response["content"]["application/problem+json"]["schema"]["$ref"],
+                    "#/components/schemas/ProblemDetails",
+                    "{method} {path} {status} is not a problem document"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn every_operation_has_a_summary() {
+    let spec = serde_json::to_value(openapi()).unwrap();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for (method, operation) in item.as_object().unwrap() {
+            assert!(operation["summary"].is_string(), "{method} {path} has no summary");
+        }
+    }
+}
+
+#[test]
+fn user_payload_schemas_declare_their_constraints() {
+    let spec = serde_json::to_value(openapi()).unwrap();
+    for schema in ["CreateUser", "UpdateUser", "UserPatch"] {
+        for field in ["firstName", "lastName"] {
+            let property = &spec["components"]["schemas"][schema]["properties"][field];
+            assert_eq!(property["pattern"], TRIMMED_TEXT, "{schema}.{field}");
+            assert_eq!(property["minLength"], 1, "{schema}.{field}");
+            assert_eq!(property["maxLength"], 100, "{schema}.{field}");
+        }
+        assert_eq!(spec["components"]["schemas"][schema]["properties"]["email"]["format"], "email", "{schema}.email");
+    }
+}
+
+#[test]
+fn business_payload_schemas_declare_their_constraints() {
+    let spec = serde_json::to_value(openapi()).unwrap();
+    let schemas = &spec["components"]["schemas"];
+    // Only used to check merged patches, so not among the components.
+    let update = serde_json::to_value(<UpdateBusiness as utoipa::PartialSchema>::schema()).unwrap();
+    for (schema, properties) in [
+        ("CreateBusiness", &schemas["CreateBusiness"]["properties"]),
+        ("UpdateBusiness", &update["properties"]),
+        ("BusinessPatch", &schemas["BusinessPatch"]["properties"]),
+    ] {
+        assert_eq!(properties["name"]["pattern"], TRIMMED_TEXT, "{schema}.name");
+        assert_eq!(properties["slug"]["pattern"], SLUG, "{schema}.slug");
+        assert_eq!(properties["slug"]["maxLength"], 63, "{schema}.slug");
+    }
+    for schema in ["BusinessSettings", "BusinessSettingsPatch"] {
+        let properties = &schemas[schema]["properties"];
+        assert_eq!(properties["timeZone"]["pattern"], TIME_ZONE, "{schema}.timeZone");
+        assert_eq!(properties["locale"]["pattern"], LANGUAGE_TAG, "{schema}.locale");
+    }
+}
+
+#[test]
+fn every_operation_requires_a_bearer_token_or_api_key() {
+    let spec = serde_json::to_value(openapi()).unwrap();
+    let scheme = &spec["components"]["securitySchemes"]["bearerAuth"];
+    assert_eq!(scheme["type"], "http");
+    assert_eq!(scheme["scheme"], "bearer");
+    assert_eq!(scheme["bearerFormat"], "JWT");
+    let scheme = &spec["components"]["securitySchemes"]["apiKey"];
+    assert_eq!(scheme["type"], "apiKey");
+    assert_eq!(scheme["in"], "header");
+    assert_eq!(scheme["name"], "X-Api-Key");
+    assert_eq!(spec["security"], serde_json::json!([{ "bearerAuth": [] }, { "apiKey": [] }]));
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for (method, operation) in item.as_object().unwrap() {
+            assert!(operation["responses"].get("401").is_some(), "{method} {path} does not document 401");
+        }
+    }
+}
+
+#[test]
+fn every_operation_declares_its_scope() {
+    let spec = serde_json::to_value(openapi()).unwrap();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for (method, operation) in item.as_object().unwrap() {
+            let resource = if path.contains("/api-keys") {
+                "api-keys"
+            } else if path.contains("/users") {
+                "users"
+            } else {
+                "businesses"
+            };
+            let access = if method == "get" { "read" } else { "write" };
+            let scope = format!("{resource}:{access}");
+            assert_eq!(
+                operation["security"],
+                serde_json::json!([{ "bearerAuth": [scope] }, { "apiKey": [scope] }]),
+                "{method} {path}"
+            );
+            assert!(operation["responses"].get("403").is_some(), "{method} {path} does not document 403");
+        }
+    }
+}
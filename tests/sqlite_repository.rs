@@ -0,0 +1,93 @@
+mod common;
+
+use rust_lambda_api_poc::repository::sqlite::SqliteStore;
+
+async fn repository() -> SqliteStore {
+    let users = SqliteStore::connect("sqlite::memory:").await.unwrap();
+    common::seed(&users).await;
+    users
+}
+
+#[tokio::test]
+async fn reads_are_scoped_to_the_business() {
+    common::reads_are_scoped_to_the_business(&repository().await).await;
+}
+
+#[tokio::test]
+async fn create_update_delete_round_trip() {
+    common::create_update_delete_round_trip(&repository().await).await;
+}
+
+#[tokio::test]
+async fn emails_are_unique_per_business() {
+    common::emails_are_unique_per_business(&repository().await).await;
+}
+
+#[tokio::test]
+async fn non_ascii_email_case_changes() {
+    common::non_ascii_email_case_changes(&repository().await).await;
+}
+
+#[tokio::test]
+async fn deleted_at_round_trips() {
+    common::deleted_at_round_trips(&repository().await).await;
+}
+
+#[tokio::test]
+async fn migrations_are_idempotent() {
+    let path = std::env::temp_dir().join(format!("users-{}.db", uuid::Uuid::new_v4()));
+    let url = format!("sqlite://{}", path.display());
+    common::seed(&SqliteStore::connect(&url).await.unwrap()).await;
+
+    let reopened = SqliteStore::connect(&url).await.unwrap();
+    common::reads_are_scoped_to_the_business(&reopened).await;
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn migrations_create_the_businesses_users_were_stored_under() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut connection = pool.acquire().await.unwrap();
+    for migration in [
+        include_str!("../migrations/20250101000000_create_users.sql"),
+        include_str!("../migrations/20250201000000_add_users_deleted_at.sql"),
+        include_str!("../migrations/20250301000000_add_users_created_at.sql"),
+        include_str!("../migrations/20250401000000_create_api_keys.sql"),
+        "INSERT INTO users (business_id, uuid, first_name, last_name, email, enabled, activated, created_at) VALUES \
+            ('7c9e6679-7425-40de-944b-e07fc1f90ae7', '550e8400-e29b-41d4-a716-446655440000', 'Jane', 'Doe', 'jane@example.com', 1, 1, '2025-02-01T00:00:00.000000Z'), \
+            ('7c9e6679-7425-40de-944b-e07fc1f90ae7', '9b2f7c1e-3d4a-4b5c-8e6f-1a2b3c4d5e6f', 'John', 'Smith', 'john@example.com', 1, 0, '2025-01-01T00:00:00.000000Z')",
+        include_str!("../migrations/20250501000000_create_businesses.sql"),
+    ] {
+        sqlx::raw_sql(migration).execute(&mut *connection).await.unwrap();
+    }
+
+    let (slug, status, settings, created_at): (String, String, String, String) =
+        sqlx::query_as("SELECT slug, status, settings, created_at FROM businesses")
+            .fetch_one(&mut *connection)
+            .await
+            .unwrap();
+    assert_eq!(slug, "7c9e6679-7425-40de-944b-e07fc1f90ae7");
+    assert_eq!(status, "active");
+    assert_eq!(settings, "{}");
+    assert_eq!(created_at, "2025-01-01T00:00:00.000000Z");
+}
+
+#[tokio::test]
+async fn concurrent_updates_are_refused() {
+    common::concurrent_updates_are_refused(&repository().await).await;
+}
+
+#[tokio::test]
+async fn query_filters_sorts_and_pages() {
+    common::query_filters_sorts_and_pages(&repository().await).await;
+}
+
+#[tokio::test]
+async fn api_keys_round_trip() {
+    common::api_keys_round_trip(&repository().await).await;
+}
+
+#[tokio::test]
+async fn businesses_round_trip() {
+    common::businesses_round_trip(&repository().await).await;
+}
@@ -0,0 +1,479 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use rust_lambda_api_poc::repository::memory::InMemoryStore;
+use rust_lambda_api_poc::repository::{RepositoryError, UserRepository};
+use rust_lambda_api_poc::search::UserSearch;
+use rust_lambda_api_poc::users::User;
+use rust_lambda_api_poc::{app, AppState};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const BUSINESS: &str = "7c9e6679-7425-40de-944b-e07fc1f90ae7";
+
+fn router() -> Router {
+    let users = InMemoryStore::from_json(include_str!("fixtures/users.json")).unwrap();
+    app(AppState::new(users).with_anonymous_access())
+}
+
+async fn send(router: &Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, axum::http::HeaderMap, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    let body = match body {
+        Some(body) => {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = router.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    (parts.status, parts.headers, body)
+}
+
+#[tokio::test]
+async fn create_user_returns_created_with_location() {
+    let router = router();
+    let (status, headers, user) = send(
+        &router,
+        Method::POST,
+        &format!("/business/{BUSINESS}/users"),
+        Some(json!({ "firstName": "Ada", "lastName": "Lovelace", "email": "ada@example.com" })),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(user["enabled"], true);
+    assert_eq!(user["activated"], false);
+    let location = headers[header::LOCATION].to_str().unwrap();
+    assert_eq!(location, format!("/business/{BUSINESS}/users/{}", user["uuid"].as_str().unwrap()));
+
+    let (status, _, fetched) = send(&router, Method::GET, location, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched, user);
+}
+
+#[tokio::test]
+async fn create_user_with_duplicate_email_conflicts() {
+    let (status, _, _) = send(
+        &router(),
+        Method::POST,
+        &format!("/business/{BUSINESS}/users"),
+        Some(json!({ "firstName": "Jane", "lastName": "Doe", "email": "jane.doe@example.com", "enabled": false })),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+const JANE: &str = "550e8400-e29b-41d4-a716-446655440000";
+
+#[tokio::test]
+async fn replace_user_overwrites_every_editable_field() {
+    let router = router();
+    let uri = format!("/business/{BUSINESS}/users/{JANE}");
+    let (status, _, user) = send(
+        &router,
+        Method::PUT,
+        &uri,
+        Some(json!({
+            "uuid": JANE,
+            "firstName": "Janet",
+            "lastName": "Doe",
+            "email": "janet.doe@example.com",
+            "enabled": false,
+            "activated": true,
+        })),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["firstName"], "Janet");
+    assert_eq!(user["enabled"], false);
+    assert_eq!(send(&router, Method::GET, &uri, None).await.2, user);
+}
+
+#[tokio::test]
+async fn replace_user_reports_every_bad_field() {
+    let (status, _, body) = send(
+        &router(),
+        Method::PUT,
+        &format!("/business/{BUSINESS}/users/{JANE}"),
+        Some(json!({
+            "uuid": "9b2f7c1e-3d4a-4b5c-8e6f-1a2b3c4d5e6f",
+            "firstName": 7,
+            "email": "jane.doe@example.com",
+            "enabled": "yes",
+            "activated": true,
+        })),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        body["errors"],
+        json!({
+            "uuid": "is immutable",
+            "firstName": "must be a string",
+            "lastName": "is required",
+            "enabled": "must be a boolean",
+        })
+    );
+}
+
+async fn merge_patch(router: &Router, uri: &str, patch: Value, content_type: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(Method::PATCH)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(patch.to_string()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn patch_user_changes_only_the_given_fields() {
+    let uri = format!("/business/{BUSINESS}/users/{JANE}");
+    let (status, user) = merge_patch(
+        &router(),
+        &uri,
+        json!({ "firstName": "Janet", "enabled": false }),
+        "application/merge-patch+json",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["firstName"], "Janet");
+    assert_eq!(user["enabled"], false);
+    assert_eq!(user["lastName"], "Doe");
+    assert_eq!(user["activated"], true);
+}
+
+#[tokio::test]
+async fn patch_user_rejects_bad_fields() {
+    let uri = format!("/business/{BUSINESS}/users/{JANE}");
+    let (status, body) = merge_patch(
+        &router(),
+        &uri,
+        json!({ "uuid": null, "lastName": null, "activated": "no" }),
+        "application/merge-patch+json",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        body["errors"],
+        json!({ "uuid": "is immutable", "lastName": "is required", "activated": "must be a boolean" })
+    );
+}
+
+#[tokio::test]
+async fn patch_user_requires_merge_patch_content_type() {
+    let uri = format!("/business/{BUSINESS}/users/{JANE}");
+    let (status, _) = merge_patch(&router(), &uri, json!({ "enabled": false }), "application/json").await;
+
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn soft_deleted_user_is_gone_until_restored() {
+    let router = router();
+    let uri = format!("/business/{BUSINESS}/users/{JANE}");
+
+    assert_eq!(send(&router, Method::DELETE, &uri, None).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(send(&router, Method::GET, &uri, None).await.0, StatusCode::GONE);
+
+    let (status, _, user) = send(&router, Method::POST, &format!("{uri}/restore"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(user.get("deletedAt").is_none());
+    assert_eq!(send(&router, Method::GET, &uri, None).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn hard_deleted_user_is_not_found() {
+    let router = router();
+    let uri = format!("/business/{BUSINESS}/users/{JANE}");
+
+    assert_eq!(send(&router, Method::DELETE, &format!("{uri}?hard=true"), None).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(send(&router, Method::GET, &uri, None).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&router, Method::POST, &format!("{uri}/restore"), None).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn list_users_pages_with_cursor() {
+    let router = router();
+    let uri = format!("/business/{BUSINESS}/users?limit=1&sort=createdAt&order=desc");
+
+    let (status, _, first) = send(&router, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(first["items"][0]["firstName"], "John");
+    let cursor = first["nextCursor"].as_str().unwrap();
+
+    let (_, _, second) = send(&router, Method::GET, &format!("{uri}&cursor={cursor}"), None).await;
+    assert_eq!(second["items"][0]["firstName"], "Jane");
+    assert!(second.get("nextCursor").is_none());
+
+    let (status, _, _) = send(
+        &router,
+        Method::GET,
+        &format!("/business/{BUSINESS}/users?limit=1&cursor={cursor}"),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+async fn create(router: &Router, first_name: &str, last_name: &str, email: &str) {
+    let body = json!({ "firstName": first_name, "lastName": last_name, "email": email });
+    let (status, _, _) = send(router, Method::POST, &format!("/business/{BUSINESS}/users"), Some(body)).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+fn names(page: &Value) -> Vec<String> {
+    page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|user| format!("{} {}", user["firstName"].as_str().unwrap(), user["lastName"].as_str().unwrap()))
+        .collect()
+}
+
+#[tokio::test]
+async fn search_users_folds_case_and_diacritics() {
+    let router = router();
+    create(&router, "Zoë", "Åberg", "zoe@example.com").await;
+
+    let (status, _, page) = send(&router, Method::GET, &format!("/business/{BUSINESS}/users/search?q=ZOE%20aber"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(&page), ["Zoë Åberg"]);
+}
+
+#[tokio::test]
+async fn search_users_finds_hyphenated_and_apostrophe_names() {
+    let router = router();
+    create(&router, "Anna", "Smith-Jones", "anna@example.com").await;
+    create(&router, "Liam", "O'Brien", "liam@example.com").await;
+
+    let (_, _, page) = send(&router, Method::GET, &format!("/business/{BUSINESS}/users/search?q=Smith-Jones"), None).await;
+    assert_eq!(names(&page), ["Anna Smith-Jones"]);
+    let (_, _, page) = send(&router, Method::GET, &format!("/business/{BUSINESS}/users/search?q=anna%20smith-jo"), None).await;
+    assert_eq!(names(&page), ["Anna Smith-Jones"]);
+    let (_, _, page) = send(&router, Method::GET, &format!("/business/{BUSINESS}/users/search?q=o%27brien"), None).await;
+    assert_eq!(names(&page), ["Liam O'Brien"]);
+}
+
+/// A store whose listing races with a write, as another request's would.
+struct WrittenDuringList {
+    users: InMemoryStore,
+    search: Arc<UserSearch>,
+}
+
+#[async_trait]
+impl UserRepository for WrittenDuringList {
+    async fn get(&self, business_id: Uuid, user_id: Uuid) -> Result<Option<User>, RepositoryError> {
+        self.users.get(business_id, user_id).await
+    }
+
+    async fn list(&self, business_id: Uuid) -> Result<Vec<User>, RepositoryError> {
+        let users = self.users.list(business_id).await;
+        self.search.invalidate(business_id);
+        users
+    }
+
+    async fn create(&self, business_id: Uuid, user: User) -> Result<User, RepositoryError> {
+        self.users.create(business_id, user).await
+    }
+
+    async fn update(&self, business_id: Uuid, user: User, expected: &User) -> Result<User, RepositoryError> {
+        self.users.update(business_id, user, expected).await
+    }
+
+    async fn delete(&self, business_id: Uuid, user_id: Uuid) -> Result<(), RepositoryError> {
+        self.users.delete(business_id, user_id).await
+    }
+}
+
+#[tokio::test]
+async fn search_index_built_before_a_write_is_not_kept() {
+    let search = Arc::new(UserSearch::default());
+    let business = BUSINESS.parse().unwrap();
+    let racing = WrittenDuringList { users: InMemoryStore::default(), search: search.clone() };
+    let stale = search.index(business, &racing).await.unwrap();
+
+    let rebuilt = search.index(business, &InMemoryStore::default()).await.unwrap();
+    assert!(!Arc::ptr_eq(&stale, &rebuilt));
+    let cached = search.index(business, &InMemoryStore::default()).await.unwrap();
+    assert!(Arc::ptr_eq(&rebuilt, &cached));
+}
+
+#[tokio::test]
+async fn search_users_ranks_exact_email_first() {
+    let router = router();
+    create(&router, "Jane", "Doenitz", "jdoenitz@example.com").await;
+    create(&router, "Doe", "Jane", "jane.doe@example.com.au").await;
+
+    let uri = format!("/business/{BUSINESS}/users/search?q=jane.doe@example.com");
+    let (_, _, page) = send(&router, Method::GET, &uri, None).await;
+    assert_eq!(names(&page), ["Jane Doe", "Doe Jane"]);
+
+    let uri = format!("/business/{BUSINESS}/users/search?q=jane%20doe");
+    let (_, _, page) = send(&router, Method::GET, &uri, None).await;
+    assert_eq!(names(&page), ["Jane Doe", "Doe Jane", "Jane Doenitz"]);
+}
+
+#[tokio::test]
+async fn search_users_pages_with_cursor() {
+    let router = router();
+    create(&router, "Jan", "Novak", "jan@example.com").await;
+    create(&router, "Janet", "Doe", "janet@example.com").await;
+
+    let uri = format!("/business/{BUSINESS}/users/search?q=jan");
+    let (_, _, all) = send(&router, Method::GET, &uri, None).await;
+    assert_eq!(names(&all).len(), 3);
+
+    let mut paged = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page_uri = match &cursor {
+            Some(cursor) => format!("{uri}&limit=1&cursor={cursor}"),
+            None => format!("{uri}&limit=1"),
+        };
+        let (_, _, page) = send(&router, Method::GET, &page_uri, None).await;
+        paged.extend(names(&page));
+        match page["nextCursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+    assert_eq!(paged, names(&all));
+}
+
+#[tokio::test]
+async fn missing_user_is_a_problem_document() {
+    let uri = format!("/business/{BUSINESS}/users/00000000-0000-0000-0000-000000000000");
+    let request = Request::builder()
+        .uri(&uri)
+        .header("x-correlation-id", "test-correlation-id")
+        .body(Body::empty())
+        .unwrap();
+    let response = router().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/problem+json");
+    assert_eq!(response.headers()["x-correlation-id"], "test-correlation-id");
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let problem: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(problem["type"], "/problems/user-not-found");
+    assert_eq!(problem["title"], "User not found");
+    assert_eq!(problem["status"], 404);
+    assert_eq!(problem["instance"], uri);
+    assert_eq!(problem["correlationId"], "test-correlation-id");
+}
+
+#[tokio::test]
+async fn user_of_missing_business_is_a_distinct_problem() {
+    let uri = format!("/business/00000000-0000-0000-0000-000000000001/users/{JANE}");
+    let (status, _, problem) = send(&router(), Method::GET, &uri, None).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(problem["type"], "/problems/business-not-found");
+    assert_eq!(problem["title"], "Business not found");
+}
+
+#[tokio::test]
+async fn malformed_path_parameter_is_a_bad_request() {
+    let (status, headers, problem) = send(&router(), Method::GET, &format!("/business/{BUSINESS}/users/not-a-uuid"), None).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(headers[header::CONTENT_TYPE], "application/problem+json");
+    assert_eq!(problem["type"], "/problems/invalid-path-parameter");
+    assert_eq!(
+        problem["errors"],
+        json!({ "userId": "must be a UUID such as 550e8400-e29b-41d4-a716-446655440000, got `not-a-uuid`" })
+    );
+}
+
+#[tokio::test]
+async fn create_user_reports_every_bad_field() {
+    let (status, _, problem) = send(
+        &router(),
+        Method::POST,
+        &format!("/business/{BUSINESS}/users"),
+        Some(json!({ "firstName": 1, "email": "ada@example.com", "enabled": "yes", "role": "admin" })),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        problem["errors"],
+        json!({
+            "firstName": "must be a string",
+            "lastName": "is required",
+            "enabled": "must be a boolean",
+            "role": "is not a known field",
+        })
+    );
+}
+
+#[tokio::test]
+async fn create_user_with_malformed_json_is_a_bad_request() {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/business/{BUSINESS}/users"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from("{\"firstName\": "))
+        .unwrap();
+    let response = router().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/problem+json");
+}
+
+#[tokio::test]
+async fn create_user_enforces_declared_constraints() {
+    let (status, _, problem) = send(
+        &router(),
+        Method::POST,
+        &format!("/business/{BUSINESS}/users"),
+        Some(json!({ "firstName": " Ada", "lastName": "L".repeat(101), "email": "ada.example.com" })),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        problem["errors"],
+        json!({
+            "firstName": "must not start or end with whitespace or contain control characters",
+            "lastName": "must be at most 100 characters",
+            "email": "must be an email address",
+        })
+    );
+}
+
+#[tokio::test]
+async fn patch_user_enforces_declared_constraints() {
+    let (status, problem) = merge_patch(
+        &router(),
+        &format!("/business/{BUSINESS}/users/{JANE}"),
+        json!({ "firstName": "", "lastName": "Doe\u{7}" }),
+        "application/merge-patch+json",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        problem["errors"],
+        json!({
+            "firstName": "must not be empty",
+            "lastName": "must not start or end with whitespace or contain control characters",
+        })
+    );
+}